@@ -1,7 +1,9 @@
 use log::info;
 
 use ethers::signers::{LocalWallet, Signer};
-use hyperliquid_rust_sdk::{BaseUrl, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient};
+use hyperliquid_rust_sdk::{
+    BaseUrl, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient, Side,
+};
 
 #[tokio::main]
 async fn main() {
@@ -33,7 +35,7 @@ async fn main() {
 
     let order = ClientOrderRequest {
         asset: "ETH".to_string(),
-        is_buy: true,
+        side: Side::Bid,
         reduce_only: false,
         limit_px: 1795.0,
         sz: 0.01,
@@ -1,5 +1,5 @@
 use ethers::types::H160;
-use hyperliquid_rust_sdk::{BaseUrl, InfoClient};
+use hyperliquid_rust_sdk::{BaseUrl, InfoClient, Interval};
 use log::info;
 use std::str::FromStr;
 
@@ -17,6 +17,13 @@ async fn main() {
     funding_history_example(&info_client).await;
     l2_snapshot_example(&info_client).await;
     candles_snapshot_example(&info_client).await;
+    user_fees_example(&info_client).await;
+    referral_example(&info_client).await;
+    predicted_fundings_example(&info_client).await;
+    max_builder_fee_example(&info_client).await;
+    delegations_example(&info_client).await;
+    delegator_summary_example(&info_client).await;
+    spot_user_state_example(&info_client).await;
 }
 
 async fn open_orders_example(info_client: &InfoClient) {
@@ -108,13 +115,81 @@ async fn candles_snapshot_example(info_client: &InfoClient) {
     let coin = "ETH";
     let start_timestamp = 1690540602225;
     let end_timestamp = 1690569402225;
-    let interval = "1h";
+    let interval = Interval::OneHour;
 
     info!(
         "Candles snapshot data for {coin} between timestamps {start_timestamp} and {end_timestamp} with interval {interval}: {:?}",
         info_client
-            .candles_snapshot(coin.to_string(), interval.to_string(), start_timestamp, end_timestamp)
+            .candles_snapshot(coin.to_string(), interval, start_timestamp, end_timestamp)
             .await
             .unwrap()
     );
 }
+
+async fn user_fees_example(info_client: &InfoClient) {
+    let user: String = "0xc64cc00b46101bd40aa1c3121195e85c0b0918d8"
+        .parse()
+        .unwrap();
+    let user = H160::from_str(&user).unwrap();
+
+    info!(
+        "User fees for {user}: {:?}",
+        info_client.user_fees(user).await.unwrap()
+    );
+}
+
+async fn referral_example(info_client: &InfoClient) {
+    let user: String = "0xc64cc00b46101bd40aa1c3121195e85c0b0918d8"
+        .parse()
+        .unwrap();
+    let user = H160::from_str(&user).unwrap();
+
+    info!(
+        "Referral state for {user}: {:?}",
+        info_client.referral(user).await.unwrap()
+    );
+}
+
+async fn predicted_fundings_example(info_client: &InfoClient) {
+    info!(
+        "Predicted fundings: {:?}",
+        info_client.predicted_fundings().await.unwrap()
+    );
+}
+
+async fn max_builder_fee_example(info_client: &InfoClient) {
+    let user = H160::from_str("0xc64cc00b46101bd40aa1c3121195e85c0b0918d8").unwrap();
+    let builder = H160::from_str("0x0d1d9635d0640821d15e323ac8adadfa9c111414").unwrap();
+
+    info!(
+        "Max builder fee approved by {user} for {builder}: {}",
+        info_client.max_builder_fee(user, builder).await.unwrap()
+    );
+}
+
+async fn delegations_example(info_client: &InfoClient) {
+    let user = H160::from_str("0xc64cc00b46101bd40aa1c3121195e85c0b0918d8").unwrap();
+
+    info!(
+        "Delegations for {user}: {:?}",
+        info_client.delegations(user).await.unwrap()
+    );
+}
+
+async fn delegator_summary_example(info_client: &InfoClient) {
+    let user = H160::from_str("0xc64cc00b46101bd40aa1c3121195e85c0b0918d8").unwrap();
+
+    info!(
+        "Delegator summary for {user}: {:?}",
+        info_client.delegator_summary(user).await.unwrap()
+    );
+}
+
+async fn spot_user_state_example(info_client: &InfoClient) {
+    let user = H160::from_str("0xc64cc00b46101bd40aa1c3121195e85c0b0918d8").unwrap();
+
+    info!(
+        "Spot balances for {user}: {:?}",
+        info_client.spot_user_state(user).await.unwrap()
+    );
+}
@@ -1,5 +1,5 @@
 use ethers::signers::{LocalWallet, Signer};
-use hyperliquid_rust_sdk::{BaseUrl, ExchangeClient, InfoClient};
+use hyperliquid_rust_sdk::{BaseUrl, ExchangeClient, InfoClient, MarginType};
 use log::info;
 
 #[tokio::main]
@@ -18,7 +18,7 @@ async fn main() {
     let info_client = InfoClient::new(None, Some(BaseUrl::Testnet)).await.unwrap();
 
     let response = exchange_client
-        .update_leverage(5, "ETH", false, None)
+        .update_leverage_typed("ETH", 5, MarginType::Isolated, None)
         .await
         .unwrap();
     info!("Update leverage response: {response:?}");
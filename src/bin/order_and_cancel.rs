@@ -3,7 +3,7 @@ use log::info;
 
 use hyperliquid_rust_sdk::{
     BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
-    ExchangeDataStatus, ExchangeResponseStatus,
+    ExchangeDataStatus, ExchangeResponseStatus, Side,
 };
 use std::{thread::sleep, time::Duration};
 
@@ -21,7 +21,7 @@ async fn main() {
 
     let order = ClientOrderRequest {
         asset: "ETH".to_string(),
-        is_buy: true,
+        side: Side::Bid,
         reduce_only: false,
         limit_px: 1800.0,
         sz: 0.01,
@@ -2,7 +2,8 @@ use ethers::signers::LocalWallet;
 use log::info;
 
 use hyperliquid_rust_sdk::{
-    BaseUrl, ClientCancelRequestCloid, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
+    BaseUrl, ClientCancelRequestCloid, ClientLimit, ClientOrder, ClientOrderRequest,
+    ExchangeClient, Side,
 };
 use std::{thread::sleep, time::Duration};
 use uuid::Uuid;
@@ -23,7 +24,7 @@ async fn main() {
     let cloid = Uuid::new_v4();
     let order = ClientOrderRequest {
         asset: "ETH".to_string(),
-        is_buy: true,
+        side: Side::Bid,
         reduce_only: false,
         limit_px: 1800.0,
         sz: 0.01,
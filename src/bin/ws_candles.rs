@@ -1,6 +1,6 @@
 use log::info;
 
-use hyperliquid_rust_sdk::{BaseUrl, InfoClient, Message, Subscription};
+use hyperliquid_rust_sdk::{BaseUrl, InfoClient, Interval, Message, Subscription};
 use tokio::{
     spawn,
     sync::mpsc::unbounded_channel,
@@ -17,7 +17,7 @@ async fn main() {
         .subscribe(
             Subscription::Candle { 
                 coin: "ETH".to_string(), 
-                interval: "1m" .to_string()
+                interval: Interval::OneMinute
             },
             sender,
         )
@@ -3,3 +3,6 @@ pub static TESTNET_API_URL: &str = "https://api.hyperliquid-testnet.xyz";
 pub static LOCAL_API_URL: &str = "http://localhost:3001";
 pub const EPSILON: f64 = 1e-9;
 pub(crate) const INF_BPS: u16 = 10_001;
+// The exchange only accepts nonces within (server time - 2 days, server time + 1 day)
+pub const NONCE_MAX_AGE_MS: u64 = 2 * 24 * 60 * 60 * 1000;
+pub const NONCE_MAX_LEAD_MS: u64 = 24 * 60 * 60 * 1000;
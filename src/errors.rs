@@ -9,7 +9,8 @@ pub enum Error {
         status_code: u16,
         error_code: Option<u16>,
         error_message: String,
-        headers: HeaderMap,
+        // Boxed to keep Error, and so every Result in the crate, small
+        headers: Box<HeaderMap>,
         error_data: Option<String>,
     },
     #[error("Server error: status code: {status_code}, error message: {error_message}")]
@@ -23,6 +24,16 @@ pub enum Error {
     ChainNotAllowed,
     #[error("Asset not found")]
     AssetNotFound,
+    #[error("Unknown asset {name}, close matches: {suggestions:?}")]
+    UnknownAsset {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    #[error("Asset name {name} is ambiguous, candidates: {candidates:?}")]
+    AmbiguousAsset {
+        name: String,
+        candidates: Vec<String>,
+    },
     #[error("Error from Eip712 struct: {0:?}")]
     Eip712(String),
     #[error("Json parse error: {0:?}")]
@@ -35,6 +46,8 @@ pub enum Error {
     Websocket(String),
     #[error("Subscription not found")]
     SubscriptionNotFound,
+    #[error("Subscription rejected: {0}")]
+    SubscriptionRejected(String),
     #[error("WS manager not instantiated")]
     WsManagerNotFound,
     #[error("WS send error: {0:?}")]
@@ -61,4 +74,14 @@ pub enum Error {
     NoCloid,
     #[error("ECDSA signature failed: {0:?}")]
     SignatureFailure(String),
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+    #[error("Exchange rejected the request: {0}")]
+    ExchangeRejected(String),
+    #[error("Exchange rejected the request: {error}, payload: {payload}")]
+    RejectedPayload { error: String, payload: String },
+    #[error("Timed out waiting for order {0} to reach a terminal state")]
+    OrderTimeout(u64),
+    #[error("No unclaimed rewards to claim")]
+    NoRewardsToClaim,
 }
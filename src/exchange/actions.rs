@@ -1,8 +1,17 @@
 use crate::{
-    exchange::{cancel::CancelRequest, order::OrderRequest},
-    signature::agent::mainnet::Agent,
+    exchange::{cancel::CancelRequest, order::OrderRequest, Actions},
+    signature::{agent::mainnet::Agent, user_signed_domain},
+};
+use ethers::{
+    abi::{encode, ParamType, Token, Tokenizable},
+    types::{
+        transaction::eip712::{
+            encode_eip712_type, make_type_hash, EIP712Domain, Eip712, Eip712Error,
+        },
+        Signature, H160, H256, U256,
+    },
+    utils::keccak256,
 };
-use ethers::types::H160;
 use serde::{Deserialize, Serialize};
 
 use super::cancel::CancelRequestCloid;
@@ -21,6 +30,29 @@ pub struct UpdateLeverage {
     pub leverage: u32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MarginType {
+    Cross,
+    Isolated,
+}
+
+impl MarginType {
+    pub fn is_cross(&self) -> bool {
+        *self == MarginType::Cross
+    }
+}
+
+impl From<bool> for MarginType {
+    fn from(is_cross: bool) -> Self {
+        if is_cross {
+            MarginType::Cross
+        } else {
+            MarginType::Isolated
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateIsolatedMargin {
@@ -29,11 +61,50 @@ pub struct UpdateIsolatedMargin {
     pub ntli: i64,
 }
 
+// How the orders of one action relate to each other
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Grouping {
+    // Independent orders
+    Na,
+    // A parent order followed by take profit / stop loss triggers sized to the parent
+    NormalTpsl,
+    // Take profit / stop loss triggers sized to the whole position
+    PositionTpsl,
+}
+
+// The signed hash is the msgpack encoding of this same struct (see Actions::hash), so every
+// order flag is serialized for the wire and for signing from a single definition
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BulkOrder {
     pub orders: Vec<OrderRequest>,
-    pub grouping: String,
+    pub grouping: Grouping,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builder: Option<BuilderInfo>,
+}
+
+// Builder the order is routed through and the fee it charges, in tenths of a basis point
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BuilderInfo {
+    #[serde(rename = "b")]
+    pub builder: H160,
+    #[serde(rename = "f")]
+    pub fee: u64,
+}
+
+// `order` replaces the resting order whole, including its cloid, which is signed along with
+// the rest of the order. A missing cloid is not carried over from the original, so set it
+// again on `order` to keep tracking the order by cloid
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModifyRequest {
+    pub oid: u64,
+    pub order: OrderRequest,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BulkModify {
+    pub modifies: Vec<ModifyRequest>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -55,3 +126,345 @@ pub struct AgentConnect {
     pub agent: Agent,
     pub agent_address: H160,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDisplayName {
+    pub display_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetReferrer {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReserveRequestWeight {
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduleCancel {
+    // Unset clears the scheduled cancel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmUserModify {
+    pub using_big_blocks: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum SpotDeploy {
+    #[serde(rename = "registerToken2")]
+    RegisterToken(RegisterToken),
+    UserGenesis(UserGenesis),
+    Genesis(Genesis),
+    RegisterSpot(RegisterSpot),
+    RegisterHyperliquidity(RegisterHyperliquidity),
+    SetDeployerTradingFeeShare(SetDeployerTradingFeeShare),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenSpec {
+    pub name: String,
+    pub sz_decimals: u32,
+    pub wei_decimals: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterToken {
+    pub spec: TokenSpec,
+    pub max_gas: u64,
+    pub full_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserGenesis {
+    pub token: u32,
+    pub user_and_wei: Vec<(H160, String)>,
+    pub existing_token_and_wei: Vec<(u32, String)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Genesis {
+    pub token: u32,
+    pub max_supply: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_hyperliquidity: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterSpot {
+    // [base, quote]
+    pub tokens: [u32; 2],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterHyperliquidity {
+    pub spot: u32,
+    pub start_px: String,
+    pub order_sz: String,
+    pub n_orders: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_seeded_levels: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDeployerTradingFeeShare {
+    pub token: u32,
+    pub share: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum PerpDeploy {
+    RegisterAsset(RegisterAsset),
+    SetOracle(SetOracle),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpAssetRequest {
+    pub coin: String,
+    pub sz_decimals: u32,
+    pub oracle_px: String,
+    pub margin_table_id: u32,
+    pub only_isolated: bool,
+}
+
+// Only needed when the asset is the first one of a new dex
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpDexSchema {
+    pub full_name: String,
+    pub collateral_token: u32,
+    pub oracle_updater: Option<H160>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterAsset {
+    pub max_gas: Option<u64>,
+    pub asset_request: PerpAssetRequest,
+    pub dex: String,
+    pub schema: Option<PerpDexSchema>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetOracle {
+    pub dex: String,
+    // (coin, px) pairs, sorted by coin
+    pub oracle_pxs: Vec<(String, String)>,
+    pub mark_pxs: Vec<Vec<(String, String)>>,
+}
+
+// Validator signer operations. The variants carry no parameters and are sent as
+// {"jailSelf": null}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum CSignerAction {
+    JailSelf(()),
+    UnjailSelf(()),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDelegate {
+    pub signature_chain_id: U256,
+    pub hyperliquid_chain: String,
+    pub validator: H160,
+    pub wei: u64,
+    pub is_undelegate: bool,
+    pub nonce: u64,
+}
+
+impl Eip712 for TokenDelegate {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(user_signed_domain(self.signature_chain_id))
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(make_type_hash(
+            "HyperliquidTransaction:TokenDelegate".to_string(),
+            &[
+                ("hyperliquidChain".to_string(), ParamType::String),
+                ("validator".to_string(), ParamType::Address),
+                ("wei".to_string(), ParamType::Uint(64)),
+                ("isUndelegate".to_string(), ParamType::Bool),
+                ("nonce".to_string(), ParamType::Uint(64)),
+            ],
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let items = vec![
+            Token::Uint(Self::type_hash()?.into()),
+            encode_eip712_type(self.hyperliquid_chain.clone().into_token()),
+            encode_eip712_type(self.validator.into_token()),
+            encode_eip712_type(self.wei.into_token()),
+            encode_eip712_type(self.is_undelegate.into_token()),
+            encode_eip712_type(self.nonce.into_token()),
+        ];
+        Ok(keccak256(encode(&items)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveBuilderFee {
+    pub signature_chain_id: U256,
+    pub hyperliquid_chain: String,
+    // Percentage, e.g. "0.01%" for 1 basis point
+    pub max_fee_rate: String,
+    pub builder: H160,
+    pub nonce: u64,
+}
+
+impl Eip712 for ApproveBuilderFee {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(user_signed_domain(self.signature_chain_id))
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(make_type_hash(
+            "HyperliquidTransaction:ApproveBuilderFee".to_string(),
+            &[
+                ("hyperliquidChain".to_string(), ParamType::String),
+                ("maxFeeRate".to_string(), ParamType::String),
+                ("builder".to_string(), ParamType::Address),
+                ("nonce".to_string(), ParamType::Uint(64)),
+            ],
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let items = vec![
+            Token::Uint(Self::type_hash()?.into()),
+            encode_eip712_type(self.hyperliquid_chain.clone().into_token()),
+            encode_eip712_type(self.max_fee_rate.clone().into_token()),
+            encode_eip712_type(self.builder.into_token()),
+            encode_eip712_type(self.nonce.into_token()),
+        ];
+        Ok(keccak256(encode(&items)))
+    }
+}
+
+// The new signer set, sent as a JSON string inside ConvertToMultiSigUser
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSigSigners {
+    pub authorized_users: Vec<H160>,
+    pub threshold: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertToMultiSigUser {
+    pub signature_chain_id: U256,
+    pub hyperliquid_chain: String,
+    // MultiSigSigners as JSON
+    pub signers: String,
+    pub nonce: u64,
+}
+
+impl Eip712 for ConvertToMultiSigUser {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(user_signed_domain(self.signature_chain_id))
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(make_type_hash(
+            "HyperliquidTransaction:ConvertToMultiSigUser".to_string(),
+            &[
+                ("hyperliquidChain".to_string(), ParamType::String),
+                ("signers".to_string(), ParamType::String),
+                ("nonce".to_string(), ParamType::Uint(64)),
+            ],
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let items = vec![
+            Token::Uint(Self::type_hash()?.into()),
+            encode_eip712_type(self.hyperliquid_chain.clone().into_token()),
+            encode_eip712_type(self.signers.clone().into_token()),
+            encode_eip712_type(self.nonce.into_token()),
+        ];
+        Ok(keccak256(encode(&items)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSigPayload {
+    pub multi_sig_user: H160,
+    // The authorized user submitting the action
+    pub outer_signer: H160,
+    pub action: Box<Actions>,
+}
+
+// An action taken by a multi-sig user, carrying the signatures of its authorized users
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSig {
+    pub signature_chain_id: U256,
+    pub signatures: Vec<Signature>,
+    pub payload: MultiSigPayload,
+}
+
+// What the outer signer signs for a MultiSig action, `multi_sig_action_hash` covering the
+// action, its signatures and the nonce
+#[derive(Debug, Clone)]
+pub struct SendMultiSig {
+    pub signature_chain_id: U256,
+    pub hyperliquid_chain: String,
+    pub multi_sig_action_hash: H256,
+    pub nonce: u64,
+}
+
+impl Eip712 for SendMultiSig {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(user_signed_domain(self.signature_chain_id))
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(make_type_hash(
+            "HyperliquidTransaction:SendMultiSig".to_string(),
+            &[
+                ("hyperliquidChain".to_string(), ParamType::String),
+                ("multiSigActionHash".to_string(), ParamType::FixedBytes(32)),
+                ("nonce".to_string(), ParamType::Uint(64)),
+            ],
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let items = vec![
+            Token::Uint(Self::type_hash()?.into()),
+            encode_eip712_type(self.hyperliquid_chain.clone().into_token()),
+            encode_eip712_type(self.multi_sig_action_hash.into_token()),
+            encode_eip712_type(self.nonce.into_token()),
+        ];
+        Ok(keccak256(encode(&items)))
+    }
+}
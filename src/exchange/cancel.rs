@@ -23,4 +23,4 @@ pub struct ClientCancelRequestCloid {
 pub struct CancelRequestCloid {
     pub asset: u32,
     pub cloid: String,
-}
\ No newline at end of file
+}
@@ -2,48 +2,86 @@ use crate::{
     consts::MAINNET_API_URL,
     exchange::{
         actions::{
-            AgentConnect, BulkCancel, BulkOrder, UpdateIsolatedMargin, UpdateLeverage, UsdcTransfer,
+            AgentConnect, ApproveBuilderFee, BuilderInfo, BulkCancel, BulkModify, BulkOrder,
+            CSignerAction, ConvertToMultiSigUser, EvmUserModify, Genesis, Grouping, MarginType,
+            ModifyRequest, MultiSig, MultiSigPayload, MultiSigSigners, PerpAssetRequest,
+            PerpDeploy, PerpDexSchema, RegisterAsset, RegisterHyperliquidity, RegisterSpot,
+            RegisterToken, ReserveRequestWeight, ScheduleCancel, SendMultiSig,
+            SetDeployerTradingFeeShare, SetDisplayName, SetOracle, SetReferrer, SpotDeploy,
+            TokenDelegate, TokenSpec, UpdateIsolatedMargin, UpdateLeverage, UsdcTransfer,
+            UserGenesis,
         },
         cancel::{CancelRequest, CancelRequestCloid},
-        ClientCancelRequest, ClientOrderRequest,
+        ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, ClientTpslOrderRequest,
+        Side,
+    },
+    helpers::{
+        agent_address_from_key, generate_random_key, next_nonce, now_timestamp_ms, round_price,
+        truncate_float, uuid_to_hex_string, EthChain, RoundingMode,
     },
-    helpers::{generate_random_key, next_nonce, uuid_to_hex_string, EthChain},
     info::info_client::InfoClient,
-    meta::Meta,
+    meta::{Meta, SpotMeta, SPOT_ASSET_OFFSET},
     prelude::*,
-    req::HttpClient,
+    req::{HttpClient, Transport},
     signature::{
-        agent::mainnet::Agent, keccak, sign_l1_action, sign_usd_transfer_action, sign_with_agent,
-        usdc_transfer::mainnet::UsdTransferSignPayload,
+        agent::mainnet::Agent, keccak, sign_l1_action, sign_typed_data, sign_usd_transfer_action,
+        sign_with_agent, usdc_transfer::mainnet::UsdTransferSignPayload,
     },
-    BaseUrl, BulkCancelCloid, Error, ExchangeResponseStatus,
+    AccountConfigReport, BaseUrl, BulkCancelCloid, CoinCancelSummary, Error, ExchangeDataStatus,
+    ExchangeDataStatuses, ExchangeErrorKind, ExchangeResponse, ExchangeResponseStatus, FilledOrder,
+    MarginRequirement, MarketOrderFill, OrderOutcome, EPSILON,
 };
 use ethers::{
     abi::AbiEncode,
     signers::{LocalWallet, Signer},
-    types::{Signature, H160, H256},
+    types::{Signature, H160, H256, U256},
+};
+use futures_util::{
+    future::{join_all, try_join},
+    stream::{self, StreamExt},
 };
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use super::cancel::ClientCancelRequestCloid;
 
+/// `ExchangeClient` is `Send + Sync` and can be shared between tasks behind an `Arc`.
+/// Every action takes `&self` and draws its nonce from a process-wide atomic counter,
+/// so concurrent calls never reuse a nonce. `meta` and the asset maps can only be
+/// replaced through `&mut self`, meaning a refresh needs exclusive access and no
+/// in-flight request can observe a half-updated asset map. Use `refresh_all_meta` to reload
+/// them; bots sharing one client can hold it in an `RwLock` to refresh in place.
 pub struct ExchangeClient {
-    pub http_client: HttpClient,
+    pub transport: Arc<dyn Transport>,
     pub wallet: LocalWallet,
     pub meta: Meta,
     pub vault_address: Option<H160>,
     pub coin_to_asset: HashMap<String, u32>,
+    pub asset_to_coin: HashMap<u32, String>,
+    pub spot_meta: Option<SpotMeta>,
+    // When set, limit orders priced more than this fraction away from the mid (0.05 = 5%)
+    // are rejected before being signed
+    pub max_slippage_guard: Option<f64>,
+    // Larger bulk_order batches are split into several signed requests
+    pub max_orders_per_request: usize,
+    // Send the chunks of a split batch concurrently instead of one after the other
+    pub concurrent_order_chunks: bool,
+    // Debug aid for signing problems: a rejected /exchange post, including an "err" status,
+    // returns Error::RejectedPayload carrying the exact JSON that was sent. Off by default
+    // since the payload contains the signature
+    pub debug_rejected_payloads: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
-struct ExchangePayload {
-    action: serde_json::Value,
-    signature: Signature,
-    nonce: u64,
-    vault_address: Option<H160>,
+pub struct ExchangePayload {
+    pub action: serde_json::Value,
+    pub signature: Signature,
+    pub nonce: u64,
+    pub vault_address: Option<H160>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_after: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -56,24 +94,263 @@ pub enum Actions {
     Order(BulkOrder),
     Cancel(BulkCancel),
     CancelByCloid(BulkCancelCloid),
+    BatchModify(BulkModify),
     Connect(AgentConnect),
+    ClaimRewards,
+    SetDisplayName(SetDisplayName),
+    SetReferrer(SetReferrer),
+    TokenDelegate(TokenDelegate),
+    ApproveBuilderFee(ApproveBuilderFee),
+    ConvertToMultiSigUser(ConvertToMultiSigUser),
+    MultiSig(MultiSig),
+    SpotDeploy(SpotDeploy),
+    PerpDeploy(PerpDeploy),
+    #[serde(rename = "CSignerAction")]
+    CSignerAction(CSignerAction),
+    ReserveRequestWeight(ReserveRequestWeight),
+    ScheduleCancel(ScheduleCancel),
+    EvmUserModify(EvmUserModify),
 }
 
 impl Actions {
     fn hash(&self, timestamp: u64, vault_address: Option<H160>) -> Result<H256> {
-        let mut bytes =
-            rmp_serde::to_vec_named(self).map_err(|e| Error::RmpParse(e.to_string()))?;
-        bytes.extend(timestamp.to_be_bytes());
-        if let Some(vault_address) = vault_address {
-            bytes.push(1);
-            bytes.extend(vault_address.to_fixed_bytes());
-        } else {
-            bytes.push(0);
+        self.hash_with_expiry(timestamp, vault_address, None)
+    }
+
+    fn hash_with_expiry(
+        &self,
+        timestamp: u64,
+        vault_address: Option<H160>,
+        expires_after: Option<u64>,
+    ) -> Result<H256> {
+        action_hash(self, timestamp, vault_address, expires_after)
+    }
+}
+
+// Multi-sig signatures hash values that aren't plain actions, so the hashing is shared
+fn action_hash(
+    action: &impl Serialize,
+    timestamp: u64,
+    vault_address: Option<H160>,
+    expires_after: Option<u64>,
+) -> Result<H256> {
+    let mut bytes = rmp_serde::to_vec_named(action).map_err(|e| Error::RmpParse(e.to_string()))?;
+    bytes.extend(timestamp.to_be_bytes());
+    if let Some(vault_address) = vault_address {
+        bytes.push(1);
+        bytes.extend(vault_address.to_fixed_bytes());
+    } else {
+        bytes.push(0);
+    }
+    if let Some(expires_after) = expires_after {
+        bytes.push(0);
+        bytes.extend(expires_after.to_be_bytes());
+    }
+    Ok(H256(ethers::utils::keccak256(bytes)))
+}
+
+const DEFAULT_SLIPPAGE: f64 = 0.05;
+
+// Every 40 orders in a request add one to its rate limit weight, and very large batches are
+// rejected as a whole, so bulk_order splits batches into requests of at most this many orders
+pub const DEFAULT_MAX_ORDERS_PER_REQUEST: usize = 40;
+
+// Per request settings shared by the order entry points
+#[derive(Clone, Debug, Default)]
+struct OrderOptions {
+    nonce: Option<u64>,
+    expires_after: Option<u64>,
+    builder: Option<BuilderInfo>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CoinConfig {
+    pub coin: String,
+    pub leverage: u32,
+    pub margin: MarginType,
+}
+
+// Account settings applied in one go by apply_config
+#[derive(Clone, Debug, Default)]
+pub struct AccountConfig {
+    pub coins: Vec<CoinConfig>,
+    // Arms a schedule_cancel this far in the future
+    pub schedule_cancel_after: Option<Duration>,
+}
+
+// Concatenates the responses of a batch split into chunks of `chunk_lens` orders, so statuses
+// stay aligned with the submitted orders. A chunk that failed as a whole reports its error
+// once per order.
+fn merge_chunk_responses(
+    chunk_lens: Vec<usize>,
+    responses: Vec<Result<ExchangeResponseStatus>>,
+) -> ExchangeResponseStatus {
+    let mut statuses = Vec::new();
+    let mut raw_statuses = Vec::new();
+    for (chunk_len, response) in chunk_lens.into_iter().zip(responses) {
+        let error = match response {
+            Ok(ExchangeResponseStatus::Ok(ExchangeResponse {
+                data: Some(data),
+                raw_data,
+                ..
+            })) => {
+                statuses.extend(data.statuses);
+                if let Some(serde_json::Value::Array(raw)) =
+                    raw_data.map(|mut raw_data| raw_data["statuses"].take())
+                {
+                    raw_statuses.extend(raw);
+                }
+                continue;
+            }
+            Ok(ExchangeResponseStatus::Ok(_)) => "order response had no statuses".to_string(),
+            Ok(ExchangeResponseStatus::Err(e)) => e,
+            Err(e) => e.to_string(),
+        };
+        statuses.extend((0..chunk_len).map(|_| ExchangeDataStatus::Error(error.clone())));
+        raw_statuses.extend((0..chunk_len).map(|_| serde_json::json!({ "error": error })));
+    }
+    ExchangeResponseStatus::Ok(ExchangeResponse {
+        response_type: "order".to_string(),
+        data: Some(ExchangeDataStatuses { statuses }),
+        raw_data: Some(serde_json::json!({ "statuses": raw_statuses })),
+    })
+}
+
+enum PlacedOrder {
+    Resting(u64),
+    Filled(FilledOrder),
+}
+
+// The single order placed or modified by the request
+fn placed_order(response: ExchangeResponseStatus) -> Result<PlacedOrder> {
+    let response = match response {
+        ExchangeResponseStatus::Ok(response) => response,
+        ExchangeResponseStatus::Err(e) => return Err(Error::ExchangeRejected(e)),
+    };
+    match response
+        .data
+        .and_then(|data| data.statuses.into_iter().next())
+    {
+        Some(ExchangeDataStatus::Resting(order)) => Ok(PlacedOrder::Resting(order.oid)),
+        Some(ExchangeDataStatus::Filled(order)) => Ok(PlacedOrder::Filled(order)),
+        Some(ExchangeDataStatus::Error(e)) => Err(Error::ExchangeRejected(e)),
+        status => Err(Error::GenericParse(format!(
+            "unexpected order status: {status:?}"
+        ))),
+    }
+}
+
+// Oid of the single order placed or modified by the request
+fn order_oid(response: ExchangeResponseStatus) -> Result<u64> {
+    Ok(match placed_order(response)? {
+        PlacedOrder::Resting(oid) => oid,
+        PlacedOrder::Filled(order) => order.oid,
+    })
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Prices are limited to 5 significant figures and 6 - sz_decimals decimals
+fn slippage_price(mid: f64, side: Side, slippage: f64, sz_decimals: u32) -> f64 {
+    let px = if side.as_is_buy() {
+        mid * (1.0 + slippage)
+    } else {
+        mid * (1.0 - slippage)
+    };
+    round_price(px, sz_decimals, RoundingMode::Nearest)
+}
+
+// Signing is pure computation, so these can run outside of an async runtime and the
+// resulting payload be sent later with `ExchangeClient::post_payload`
+pub fn sign_action(
+    action: &Actions,
+    wallet: &LocalWallet,
+    nonce: u64,
+    vault_address: Option<H160>,
+    expires_after: Option<u64>,
+    is_mainnet: bool,
+) -> Result<Signature> {
+    let connection_id = action.hash_with_expiry(nonce, vault_address, expires_after)?;
+    sign_l1_action(wallet, connection_id, is_mainnet)
+}
+
+pub fn build_l1_payload(
+    action: &Actions,
+    wallet: &LocalWallet,
+    nonce: u64,
+    vault_address: Option<H160>,
+    expires_after: Option<u64>,
+    is_mainnet: bool,
+) -> Result<ExchangePayload> {
+    let signature = sign_action(
+        action,
+        wallet,
+        nonce,
+        vault_address,
+        expires_after,
+        is_mainnet,
+    )?;
+    let action = serde_json::to_value(action).map_err(|e| Error::JsonParse(e.to_string()))?;
+    Ok(ExchangePayload {
+        action,
+        signature,
+        nonce,
+        vault_address,
+        expires_after,
+    })
+}
+
+fn insert_spot_assets(
+    spot_meta: &SpotMeta,
+    coin_to_asset: &mut HashMap<String, u32>,
+    asset_to_coin: &mut HashMap<u32, String>,
+) {
+    let mut pair_names: HashMap<String, Vec<u32>> = HashMap::new();
+    for spot_asset in &spot_meta.universe {
+        let asset = SPOT_ASSET_OFFSET + spot_asset.index;
+        coin_to_asset.insert(spot_asset.name.clone(), asset);
+        asset_to_coin.insert(asset, spot_asset.name.clone());
+        if let Some(pair_name) = spot_meta.pair_name(spot_asset) {
+            pair_names.entry(pair_name).or_default().push(asset);
+        }
+    }
+    for (pair_name, assets) in pair_names {
+        if let [asset] = assets[..] {
+            coin_to_asset.entry(pair_name).or_insert(asset);
         }
-        Ok(H256(ethers::utils::keccak256(bytes)))
     }
 }
 
+// (coin_to_asset, asset_to_coin) for the perps in `meta` and the pairs in `spot_meta`
+fn asset_maps(
+    meta: &Meta,
+    spot_meta: Option<&SpotMeta>,
+) -> (HashMap<String, u32>, HashMap<u32, String>) {
+    let mut coin_to_asset = HashMap::new();
+    let mut asset_to_coin = HashMap::new();
+    for (asset_ind, asset) in meta.universe.iter().enumerate() {
+        coin_to_asset.insert(asset.name.clone(), asset_ind as u32);
+        asset_to_coin.insert(asset_ind as u32, asset.name.clone());
+    }
+    if let Some(spot_meta) = spot_meta {
+        insert_spot_assets(spot_meta, &mut coin_to_asset, &mut asset_to_coin);
+    }
+    (coin_to_asset, asset_to_coin)
+}
+
 impl ExchangeClient {
     pub async fn new(
         client: Option<Client>,
@@ -82,56 +359,238 @@ impl ExchangeClient {
         meta: Option<Meta>,
         vault_address: Option<H160>,
     ) -> Result<ExchangeClient> {
-        let client = client.unwrap_or_default();
+        let client = match client {
+            Some(client) => client,
+            None => HttpClient::build_client(None)?,
+        };
         let base_url = base_url.unwrap_or(BaseUrl::Mainnet);
 
+        let transport = Arc::new(HttpClient {
+            client,
+            base_url: base_url.get_url(),
+            redact_logs: false,
+            in_flight: HttpClient::default_in_flight(),
+        });
+        ExchangeClient::with_transport(transport, wallet, meta, vault_address).await
+    }
+
+    pub async fn with_transport(
+        transport: Arc<dyn Transport>,
+        wallet: LocalWallet,
+        meta: Option<Meta>,
+        vault_address: Option<H160>,
+    ) -> Result<ExchangeClient> {
         let meta = if let Some(meta) = meta {
             meta
         } else {
-            let info = InfoClient::new(None, Some(base_url)).await?;
+            let info = InfoClient::with_transport(Arc::clone(&transport));
             info.meta().await?
         };
 
-        let mut coin_to_asset = HashMap::new();
-        for (asset_ind, asset) in meta.universe.iter().enumerate() {
-            coin_to_asset.insert(asset.name.clone(), asset_ind as u32);
-        }
+        let (coin_to_asset, asset_to_coin) = asset_maps(&meta, None);
 
         Ok(ExchangeClient {
             wallet,
             meta,
             vault_address,
-            http_client: HttpClient {
-                client,
-                base_url: base_url.get_url(),
-            },
+            transport,
             coin_to_asset,
+            asset_to_coin,
+            spot_meta: None,
+            max_slippage_guard: None,
+            max_orders_per_request: DEFAULT_MAX_ORDERS_PER_REQUEST,
+            concurrent_order_chunks: false,
+            debug_rejected_payloads: false,
         })
     }
 
+    // Makes spot pairs tradable by their canonical name ("PURR/USDC", "@1") as well as by
+    // "BASE/QUOTE" for non canonical pairs, when that name is unique
+    pub async fn load_spot_meta(&mut self) -> Result<()> {
+        let spot_meta = self.info_client().spot_meta().await?;
+        insert_spot_assets(&spot_meta, &mut self.coin_to_asset, &mut self.asset_to_coin);
+        self.spot_meta = Some(spot_meta);
+        Ok(())
+    }
+
+    // Refetches perp and spot meta and rebuilds every asset map from them. Both are fetched
+    // before anything is replaced, so a failed request leaves the client as it was, and the
+    // maps never mix indices from different fetches. Coins delisted since the last load are
+    // dropped from the maps
+    pub async fn refresh_all_meta(&mut self) -> Result<()> {
+        let info = self.info_client();
+        let (meta, spot_meta) = try_join(info.meta(), info.spot_meta()).await?;
+
+        let (coin_to_asset, asset_to_coin) = asset_maps(&meta, Some(&spot_meta));
+        self.meta = meta;
+        self.spot_meta = Some(spot_meta);
+        self.coin_to_asset = coin_to_asset;
+        self.asset_to_coin = asset_to_coin;
+        Ok(())
+    }
+
+    // Looks up a coin or spot pair by exact name, then case insensitively
+    pub fn resolve_asset(&self, name: &str) -> Result<u32> {
+        if let Some(&asset) = self.coin_to_asset.get(name) {
+            return Ok(asset);
+        }
+
+        let lowercase_name = name.to_lowercase();
+        let pair_names = self.spot_meta.iter().flat_map(|spot_meta| {
+            spot_meta.universe.iter().filter_map(|spot_asset| {
+                let pair_name = spot_meta.pair_name(spot_asset)?;
+                Some((pair_name, SPOT_ASSET_OFFSET + spot_asset.index))
+            })
+        });
+        let mut candidates: Vec<u32> = self
+            .coin_to_asset
+            .iter()
+            .map(|(coin, &asset)| (coin.clone(), asset))
+            .chain(pair_names)
+            .filter(|(coin, _)| coin.to_lowercase() == lowercase_name)
+            .map(|(_, asset)| asset)
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        match candidates[..] {
+            [asset] => Ok(asset),
+            [] => {
+                let mut suggestions: Vec<(usize, &String)> = self
+                    .coin_to_asset
+                    .keys()
+                    .filter_map(|coin| {
+                        let lowercase_coin = coin.to_lowercase();
+                        let distance = edit_distance(&lowercase_coin, &lowercase_name);
+                        let is_close = distance <= 2
+                            || lowercase_coin.contains(&lowercase_name)
+                            || lowercase_name.contains(&lowercase_coin);
+                        is_close.then_some((distance, coin))
+                    })
+                    .collect();
+                suggestions.sort();
+                Err(Error::UnknownAsset {
+                    name: name.to_string(),
+                    suggestions: suggestions
+                        .into_iter()
+                        .take(5)
+                        .map(|(_, coin)| coin.clone())
+                        .collect(),
+                })
+            }
+            _ => Err(Error::AmbiguousAsset {
+                name: name.to_string(),
+                candidates: candidates
+                    .iter()
+                    .filter_map(|asset| self.asset_to_coin.get(asset).cloned())
+                    .collect(),
+            }),
+        }
+    }
+
+    pub fn asset_index(&self, coin: &str) -> Option<u32> {
+        self.coin_to_asset.get(coin).copied()
+    }
+
+    pub fn asset_name(&self, index: u32) -> Option<&str> {
+        self.asset_to_coin.get(&index).map(String::as_str)
+    }
+
+    // Perp coins whose index on the server no longer matches the cached one, including coins
+    // that were added or removed since `meta` was loaded. Empty when the cache is current.
+    pub async fn verify_meta(&self) -> Result<Vec<String>> {
+        let meta = self.info_client().meta().await?;
+        let server_assets: HashMap<&str, u32> = meta
+            .universe
+            .iter()
+            .enumerate()
+            .map(|(asset_ind, asset)| (asset.name.as_str(), asset_ind as u32))
+            .collect();
+
+        let mut changed: Vec<String> = self
+            .coin_to_asset
+            .iter()
+            .filter(|(_, &asset)| asset < SPOT_ASSET_OFFSET)
+            .filter(|(coin, &asset)| server_assets.get(coin.as_str()) != Some(&asset))
+            .map(|(coin, _)| coin.clone())
+            .collect();
+        changed.extend(
+            server_assets
+                .keys()
+                .filter(|coin| !self.coin_to_asset.contains_key(**coin))
+                .map(|coin| coin.to_string()),
+        );
+        changed.sort();
+        Ok(changed)
+    }
+
+    pub(crate) fn info_client(&self) -> InfoClient {
+        InfoClient::with_transport(Arc::clone(&self.transport))
+    }
+
     async fn post(
         &self,
         action: serde_json::Value,
         signature: Signature,
         nonce: u64,
+    ) -> Result<ExchangeResponseStatus> {
+        self.post_with_vault(action, signature, nonce, self.vault_address)
+            .await
+    }
+
+    async fn post_with_vault(
+        &self,
+        action: serde_json::Value,
+        signature: Signature,
+        nonce: u64,
+        vault_address: Option<H160>,
     ) -> Result<ExchangeResponseStatus> {
         let exchange_payload = ExchangePayload {
             action,
             signature,
             nonce,
-            vault_address: self.vault_address,
+            vault_address,
+            expires_after: None,
         };
-        let res = serde_json::to_string(&exchange_payload)
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        self.post_payload(&exchange_payload).await
+    }
 
-        serde_json::from_str(
-            &self
-                .http_client
-                .post("/exchange", res)
-                .await
-                .map_err(|e| Error::JsonParse(e.to_string()))?,
-        )
-        .map_err(|e| Error::JsonParse(e.to_string()))
+    pub async fn post_payload(
+        &self,
+        exchange_payload: &ExchangePayload,
+    ) -> Result<ExchangeResponseStatus> {
+        let res =
+            serde_json::to_string(exchange_payload).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let payload = self.debug_rejected_payloads.then(|| res.clone());
+
+        let response = self
+            .transport
+            .post("/exchange", res)
+            .await
+            .map_err(|e| Error::JsonParse(e.to_string()))
+            .and_then(|body| {
+                serde_json::from_str(&body).map_err(|e| Error::JsonParse(e.to_string()))
+            });
+
+        match (payload, response) {
+            (Some(payload), Err(e)) => Err(Error::RejectedPayload {
+                error: e.to_string(),
+                payload,
+            }),
+            (Some(payload), Ok(ExchangeResponseStatus::Err(e))) => {
+                Err(Error::RejectedPayload { error: e, payload })
+            }
+            (_, response) => response,
+        }
+    }
+
+    // hyperliquidChain and signatureChainId for actions signed directly by the user
+    fn user_signed_chain(&self) -> (String, U256) {
+        if self.transport.base_url() == MAINNET_API_URL {
+            ("Mainnet".to_string(), U256::from(42161))
+        } else {
+            ("Testnet".to_string(), U256::from(421614))
+        }
     }
 
     pub async fn usdc_transfer(
@@ -141,7 +600,7 @@ impl ExchangeClient {
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
         let wallet = wallet.unwrap_or(&self.wallet);
-        let (chain, l1_name) = if self.http_client.base_url.eq(MAINNET_API_URL) {
+        let (chain, l1_name) = if self.transport.base_url().eq(MAINNET_API_URL) {
             (EthChain::Arbitrum, "Arbitrum".to_string())
         } else {
             (EthChain::ArbitrumGoerli, "ArbitrumGoerli".to_string())
@@ -172,333 +631,3295 @@ impl ExchangeClient {
         self.bulk_order(vec![order], wallet).await
     }
 
+    // Places `order` and waits up to `timeout` for it to finish, polling order_status once it
+    // rests. An order that fills on placement returns without polling. On timeout the order
+    // is left resting and Error::OrderTimeout carries its oid
+    pub async fn order_and_wait(
+        &self,
+        order: ClientOrderRequest,
+        timeout: Duration,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<OrderOutcome> {
+        let oid = match placed_order(self.order(order, wallet).await?)? {
+            PlacedOrder::Resting(oid) => oid,
+            PlacedOrder::Filled(filled) => return Ok(OrderOutcome::Filled(filled)),
+        };
+
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        self.info_client()
+            .await_order_terminal(address, oid, timeout)
+            .await
+            .map(OrderOutcome::Terminal)
+    }
+
     pub async fn bulk_order(
         &self,
         orders: Vec<ClientOrderRequest>,
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
-
-        let mut transformed_orders = Vec::new();
-
-        for order in orders {
-            transformed_orders.push(order.convert(&self.coin_to_asset)?);
-        }
-
-        let action = Actions::Order(BulkOrder {
-            orders: transformed_orders,
-            grouping: "na".to_string(),
-        });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        self.bulk_order_with_expiry(orders, None, wallet).await
+    }
 
-        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
-        self.post(action, signature, timestamp).await
+    // `expires_after` is a unix timestamp in milliseconds after which the exchange rejects the
+    // request instead of placing the orders. It bounds when the request may land, it does not
+    // cancel orders that already rest on the book. A time already in the past is rejected.
+    pub async fn bulk_order_with_expiry(
+        &self,
+        orders: Vec<ClientOrderRequest>,
+        expires_after: Option<u64>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let options = OrderOptions {
+            expires_after,
+            ..Default::default()
+        };
+        self.bulk_order_inner(orders, options, wallet).await
     }
 
-    pub async fn cancel(
+    // Signs with `nonce` instead of the current time, so the same orders always produce the same
+    // payload. Batches over `max_orders_per_request` use consecutive nonces from `nonce` on.
+    pub async fn bulk_order_with_nonce(
         &self,
-        cancel: ClientCancelRequest,
+        orders: Vec<ClientOrderRequest>,
+        nonce: u64,
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
-        self.bulk_cancel(vec![cancel], wallet).await
+        let options = OrderOptions {
+            nonce: Some(nonce),
+            ..Default::default()
+        };
+        self.bulk_order_inner(orders, options, wallet).await
     }
 
-    pub async fn bulk_cancel(
+    async fn bulk_order_inner(
         &self,
-        cancels: Vec<ClientCancelRequest>,
+        orders: Vec<ClientOrderRequest>,
+        options: OrderOptions,
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        self.check_slippage_guard(&orders).await?;
+        let chunk_size = self.max_orders_per_request.max(1);
+        if orders.len() <= chunk_size {
+            let payload = self.build_order_payload_inner(orders, &options, wallet)?;
+            return self.post_payload(&payload).await;
+        }
 
-        let mut transformed_cancels = Vec::new();
-        for cancel in cancels.into_iter() {
-            let &asset = self
-                .coin_to_asset
-                .get(&cancel.asset)
-                .ok_or(Error::AssetNotFound)?;
-            transformed_cancels.push(CancelRequest {
-                asset,
-                oid: cancel.oid,
-            });
+        // Everything is signed up front so an invalid order fails the batch before any of it
+        // is sent
+        let mut chunk_lens = Vec::new();
+        let mut payloads = Vec::new();
+        let mut orders = orders.into_iter().peekable();
+        while orders.peek().is_some() {
+            let chunk: Vec<ClientOrderRequest> = orders.by_ref().take(chunk_size).collect();
+            let chunk_options = OrderOptions {
+                nonce: options.nonce.map(|nonce| nonce + chunk_lens.len() as u64),
+                ..options.clone()
+            };
+            chunk_lens.push(chunk.len());
+            payloads.push(self.build_order_payload_inner(chunk, &chunk_options, wallet)?);
         }
 
-        let action = Actions::Cancel(BulkCancel {
-            cancels: transformed_cancels,
-        });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
+        let responses = if self.concurrent_order_chunks {
+            join_all(payloads.iter().map(|payload| self.post_payload(payload))).await
+        } else {
+            let mut responses = Vec::new();
+            for payload in &payloads {
+                responses.push(self.post_payload(payload).await);
+            }
+            responses
+        };
+        Ok(merge_chunk_responses(chunk_lens, responses))
+    }
 
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
-        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+    async fn check_slippage_guard(&self, orders: &[ClientOrderRequest]) -> Result<()> {
+        let Some(max_slippage) = self.max_slippage_guard else {
+            return Ok(());
+        };
+        let limit_orders: Vec<&ClientOrderRequest> = orders
+            .iter()
+            .filter(|order| matches!(order.order_type, ClientOrder::Limit(_)))
+            .collect();
+        if limit_orders.is_empty() {
+            return Ok(());
+        }
 
-        self.post(action, signature, timestamp).await
+        let mids = self.info_client().all_mids().await?;
+        for order in limit_orders {
+            let asset = self.resolve_asset(&order.asset)?;
+            let mid: f64 = self
+                .asset_to_coin
+                .get(&asset)
+                .and_then(|coin| mids.get(coin))
+                .ok_or_else(|| {
+                    Error::InvalidOrder(format!("no mid price to check {} against", order.asset))
+                })?
+                .parse()
+                .map_err(|_| Error::FloatStringParse)?;
+            let slippage = (order.limit_px - mid).abs() / mid;
+            if slippage > max_slippage {
+                return Err(Error::InvalidOrder(format!(
+                    "limit_px {} is {:.2}% away from the {} mid {mid}, above the {:.2}% guard",
+                    order.limit_px,
+                    slippage * 100.0,
+                    order.asset,
+                    max_slippage * 100.0
+                )));
+            }
+        }
+        Ok(())
     }
 
-    pub async fn cancel_by_cloid(
+    // Signs `action` with a fresh nonce and the client's vault address and network
+    pub fn build_l1_action_payload(
         &self,
-        cancel: ClientCancelRequestCloid,
+        action: &Actions,
         wallet: Option<&LocalWallet>,
-    ) -> Result<ExchangeResponseStatus> {
-        self.bulk_cancel_by_cloid(vec![cancel], wallet).await
+    ) -> Result<ExchangePayload> {
+        self.build_l1_action_payload_inner(action, None, None, wallet)
     }
 
-    pub async fn bulk_cancel_by_cloid(
+    // Same as `build_l1_action_payload` with a caller chosen nonce, for reproducible signatures
+    // or replaying a specific action
+    pub fn build_l1_action_payload_with_nonce(
         &self,
-        cancels: Vec<ClientCancelRequestCloid>,
+        action: &Actions,
+        nonce: u64,
         wallet: Option<&LocalWallet>,
-    ) -> Result<ExchangeResponseStatus> {
+    ) -> Result<ExchangePayload> {
+        self.build_l1_action_payload_inner(action, Some(nonce), None, wallet)
+    }
+
+    fn build_l1_action_payload_inner(
+        &self,
+        action: &Actions,
+        nonce: Option<u64>,
+        expires_after: Option<u64>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangePayload> {
         let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let is_mainnet = self.transport.base_url() == MAINNET_API_URL;
+        build_l1_payload(
+            action,
+            wallet,
+            nonce.unwrap_or_else(next_nonce),
+            self.vault_address,
+            expires_after,
+            is_mainnet,
+        )
+    }
 
-        let mut transformed_cancels: Vec<CancelRequestCloid> = Vec::new();
-        for cancel in cancels.into_iter() {
-            let &asset = self
-                .coin_to_asset
-                .get(&cancel.asset)
-                .ok_or(Error::AssetNotFound)?;
-            transformed_cancels.push(CancelRequestCloid {
-                asset,
-                cloid: uuid_to_hex_string(cancel.cloid),
-            });
+    pub fn build_order_payload(
+        &self,
+        orders: Vec<ClientOrderRequest>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangePayload> {
+        self.build_order_payload_with_expiry(orders, None, wallet)
+    }
+
+    pub fn build_order_payload_with_expiry(
+        &self,
+        orders: Vec<ClientOrderRequest>,
+        expires_after: Option<u64>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangePayload> {
+        let options = OrderOptions {
+            expires_after,
+            ..Default::default()
+        };
+        self.build_order_payload_inner(orders, &options, wallet)
+    }
+
+    fn build_order_payload_inner(
+        &self,
+        orders: Vec<ClientOrderRequest>,
+        options: &OrderOptions,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangePayload> {
+        let mut transformed_orders = Vec::new();
+
+        for order in orders {
+            let asset = self.resolve_asset(&order.asset)?;
+            transformed_orders.push(order.convert(asset)?);
         }
 
-        let action = Actions::CancelByCloid(BulkCancelCloid {
-            cancels: transformed_cancels,
+        let action = Actions::Order(BulkOrder {
+            orders: transformed_orders,
+            grouping: Grouping::Na,
+            builder: options.builder.clone(),
         });
+        self.build_l1_action_payload_inner(&action, options.nonce, options.expires_after, wallet)
+    }
 
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
-        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+    // Routes the order through `builder`, who charges `fee` in tenths of a basis point. When the
+    // fee is above what the user approved for that builder, it is approved first. Returns the
+    // approval response, if one was needed, along with the order response.
+    pub async fn order_via_builder(
+        &self,
+        order: ClientOrderRequest,
+        builder: H160,
+        fee: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<(Option<ExchangeResponseStatus>, ExchangeResponseStatus)> {
+        let user = wallet.unwrap_or(&self.wallet).address();
+        let approved = self.info_client().max_builder_fee(user, builder).await?;
+        let approval = if approved < fee {
+            match self.approve_builder_fee(builder, fee, wallet).await? {
+                ExchangeResponseStatus::Err(e) => return Err(Error::ExchangeRejected(e)),
+                approval => Some(approval),
+            }
+        } else {
+            None
+        };
 
-        self.post(action, signature, timestamp).await
+        let options = OrderOptions {
+            builder: Some(BuilderInfo { builder, fee }),
+            ..Default::default()
+        };
+        let response = self.bulk_order_inner(vec![order], options, wallet).await?;
+        Ok((approval, response))
     }
 
-    pub async fn update_leverage(
+    // Lets `builder` charge up to `max_fee` tenths of a basis point on orders routed through it.
+    // Must be signed by the account itself, agents can't approve builders.
+    pub async fn approve_builder_fee(
         &self,
-        leverage: u32,
-        coin: &str,
-        is_cross: bool,
+        builder: H160,
+        max_fee: u64,
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
         let wallet = wallet.unwrap_or(&self.wallet);
-
         let timestamp = next_nonce();
 
-        let &asset_index = self.coin_to_asset.get(coin).ok_or(Error::AssetNotFound)?;
-        let action = Actions::UpdateLeverage(UpdateLeverage {
-            asset: asset_index,
-            is_cross,
-            leverage,
-        });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
-        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let (hyperliquid_chain, signature_chain_id) = self.user_signed_chain();
+        let approve_builder_fee = ApproveBuilderFee {
+            signature_chain_id,
+            hyperliquid_chain,
+            max_fee_rate: format!("{}%", max_fee as f64 / 1000.0),
+            builder,
+            nonce: timestamp,
+        };
+        let signature = sign_typed_data(&approve_builder_fee, wallet)?;
+        let action = serde_json::to_value(Actions::ApproveBuilderFee(approve_builder_fee))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        self.post(action, signature, timestamp).await
+        self.post_with_vault(action, signature, timestamp, None)
+            .await
     }
 
-    pub async fn update_isolated_margin(
+    // Makes the signing account a multi-sig user controlled by `authorized_users`, `threshold`
+    // of which must sign each of its actions
+    pub async fn convert_to_multi_sig_user(
         &self,
-        amount: f64,
-        coin: &str,
+        mut authorized_users: Vec<H160>,
+        threshold: u32,
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
         let wallet = wallet.unwrap_or(&self.wallet);
-
-        let amount = (amount * 1_000_000.0).round() as i64;
         let timestamp = next_nonce();
 
-        let &asset_index = self.coin_to_asset.get(coin).ok_or(Error::AssetNotFound)?;
-        let action = Actions::UpdateIsolatedMargin(UpdateIsolatedMargin {
-            asset: asset_index,
-            is_buy: true,
-            ntli: amount,
+        authorized_users.sort();
+        let signers = serde_json::to_string(&MultiSigSigners {
+            authorized_users,
+            threshold,
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let (hyperliquid_chain, signature_chain_id) = self.user_signed_chain();
+        let convert = ConvertToMultiSigUser {
+            signature_chain_id,
+            hyperliquid_chain,
+            signers,
+            nonce: timestamp,
+        };
+        let signature = sign_typed_data(&convert, wallet)?;
+        let action = serde_json::to_value(Actions::ConvertToMultiSigUser(convert))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        self.post_with_vault(action, signature, timestamp, None)
+            .await
+    }
+
+    // One authorized user's signature of `action` on behalf of `multi_sig_user`, to be passed
+    // to multi_sig by `outer_signer`. Every signer has to use the same `nonce`. Only L1 actions
+    // (orders, cancels, leverage, ...) can be signed this way for now
+    pub fn sign_multi_sig_l1_action(
+        &self,
+        action: &Actions,
+        multi_sig_user: H160,
+        outer_signer: H160,
+        nonce: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<Signature> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        let connection_id = action_hash(
+            &(multi_sig_user, outer_signer, action),
+            nonce,
+            self.vault_address,
+            None,
+        )?;
+        let is_mainnet = self.transport.base_url() == MAINNET_API_URL;
+        sign_l1_action(wallet, connection_id, is_mainnet)
+    }
+
+    // Submits `action` for `multi_sig_user` with the `signatures` collected from its
+    // authorized users through sign_multi_sig_l1_action. The submitting wallet is the outer
+    // signer and must be one of the authorized users
+    pub async fn multi_sig(
+        &self,
+        multi_sig_user: H160,
+        action: Actions,
+        signatures: Vec<Signature>,
+        nonce: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+
+        let (hyperliquid_chain, signature_chain_id) = self.user_signed_chain();
+        let multi_sig = MultiSig {
+            signature_chain_id,
+            signatures,
+            payload: MultiSigPayload {
+                multi_sig_user,
+                outer_signer: wallet.address(),
+                action: Box::new(action),
+            },
+        };
+        // The hash covers the action without its "type" tag
+        let multi_sig_action_hash = action_hash(&multi_sig, nonce, self.vault_address, None)?;
+        let signature = sign_typed_data(
+            &SendMultiSig {
+                signature_chain_id,
+                hyperliquid_chain,
+                multi_sig_action_hash,
+                nonce,
+            },
+            wallet,
+        )?;
+        let action = serde_json::to_value(Actions::MultiSig(multi_sig))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        self.post(action, signature, nonce).await
+    }
+
+    // Moves resting order `oid` to `new_order` with a modify, returning the oid it now rests
+    // under. Orders that can't be modified in place are canceled and placed again, but only
+    // once the cancel went through. The order's cloid is whatever `new_order` carries, see
+    // ModifyRequest.
+    pub async fn replace_order(
+        &self,
+        oid: u64,
+        new_order: ClientOrderRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<u64> {
+        self.check_slippage_guard(std::slice::from_ref(&new_order))
+            .await?;
+        let coin = new_order.asset.clone();
+        let asset = self.resolve_asset(&coin)?;
+        let order = new_order.convert(asset)?;
+
+        let action = Actions::BatchModify(BulkModify {
+            modifies: vec![ModifyRequest {
+                oid,
+                order: order.clone(),
+            }],
+        });
+        let payload = self.build_l1_action_payload(&action, wallet)?;
+        let modify_error = match order_oid(self.post_payload(&payload).await?) {
+            Ok(new_oid) => return Ok(new_oid),
+            Err(Error::ExchangeRejected(e))
+                if ExchangeErrorKind::classify(&e) == ExchangeErrorKind::ModifyUnsupported =>
+            {
+                Error::ExchangeRejected(e)
+            }
+            Err(e) => return Err(e),
+        };
+
+        let response = self
+            .cancel(ClientCancelRequest { asset: coin, oid }, wallet)
+            .await?;
+        let canceled = matches!(
+            response,
+            ExchangeResponseStatus::Ok(ExchangeResponse { data: Some(ref data), .. })
+                if matches!(data.statuses.first(), Some(ExchangeDataStatus::Success))
+        );
+        if !canceled {
+            return Err(modify_error);
+        }
+
+        let action = Actions::Order(BulkOrder {
+            orders: vec![order],
+            grouping: Grouping::Na,
+            builder: None,
+        });
+        let payload = self.build_l1_action_payload(&action, wallet)?;
+        order_oid(self.post_payload(&payload).await?)
+    }
+
+    // Places the parent order together with its take profit and stop loss triggers
+    pub async fn tpsl_order(
+        &self,
+        order: ClientTpslOrderRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.check_slippage_guard(std::slice::from_ref(&order.parent))
+            .await?;
+        let payload = self.build_tpsl_order_payload(order, wallet)?;
+        self.post_payload(&payload).await
+    }
+
+    pub fn build_tpsl_order_payload(
+        &self,
+        order: ClientTpslOrderRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangePayload> {
+        let sz_decimals = self
+            .meta
+            .universe
+            .iter()
+            .find(|asset| asset.name == order.parent.asset)
+            .ok_or(Error::AssetNotFound)?
+            .sz_decimals;
+        // Market triggers still carry a limit price, which bounds the slippage once triggered
+        let orders = order.into_orders(|trigger_px, child_side| {
+            slippage_price(trigger_px, child_side, DEFAULT_SLIPPAGE, sz_decimals)
+        });
+
+        let mut transformed_orders = Vec::new();
+        for order in orders {
+            let asset = self.resolve_asset(&order.asset)?;
+            transformed_orders.push(order.convert(asset)?);
+        }
+
+        let action = Actions::Order(BulkOrder {
+            orders: transformed_orders,
+            grouping: Grouping::NormalTpsl,
+            builder: None,
+        });
+        self.build_l1_action_payload(&action, wallet)
+    }
+
+    pub async fn cancel(
+        &self,
+        cancel: ClientCancelRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.bulk_cancel(vec![cancel], wallet).await
+    }
+
+    pub async fn bulk_cancel(
+        &self,
+        cancels: Vec<ClientCancelRequest>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let payload = self.build_cancel_payload(cancels, wallet)?;
+        self.post_payload(&payload).await
+    }
+
+    pub async fn bulk_cancel_with_nonce(
+        &self,
+        cancels: Vec<ClientCancelRequest>,
+        nonce: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = self.cancel_action(cancels)?;
+        let payload = self.build_l1_action_payload_with_nonce(&action, nonce, wallet)?;
+        self.post_payload(&payload).await
+    }
+
+    pub fn build_cancel_payload(
+        &self,
+        cancels: Vec<ClientCancelRequest>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangePayload> {
+        let action = self.cancel_action(cancels)?;
+        self.build_l1_action_payload(&action, wallet)
+    }
+
+    fn cancel_action(&self, cancels: Vec<ClientCancelRequest>) -> Result<Actions> {
+        let mut transformed_cancels = Vec::new();
+        for cancel in cancels.into_iter() {
+            let asset = self.resolve_asset(&cancel.asset)?;
+            transformed_cancels.push(CancelRequest {
+                asset,
+                oid: cancel.oid,
+            });
+        }
+
+        Ok(Actions::Cancel(BulkCancel {
+            cancels: transformed_cancels,
+        }))
+    }
+
+    // There is no cancel all action, so this cancels every order open on `coin` by oid in one
+    // bulk cancel. None when nothing was open.
+    pub async fn cancel_all_for_asset(
+        &self,
+        coin: &str,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<Option<ExchangeResponseStatus>> {
+        let asset = self.resolve_asset(coin)?;
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        let cancels: Vec<ClientCancelRequest> = self
+            .info_client()
+            .open_orders(address)
+            .await?
+            .into_iter()
+            .filter(|order| self.resolve_asset(&order.coin).ok() == Some(asset))
+            .map(|order| ClientCancelRequest {
+                asset: order.coin,
+                oid: order.oid,
+            })
+            .collect();
+        if cancels.is_empty() {
+            return Ok(None);
+        }
+
+        self.bulk_cancel(cancels, wallet).await.map(Some)
+    }
+
+    /// Cancels every open order of `address`, one `bulk_cancel` per coin. At most
+    /// `max_concurrent` requests are in flight at once to stay within rate limits.
+    /// Summaries are sorted by coin.
+    pub async fn cancel_all_concurrent(
+        &self,
+        address: H160,
+        max_concurrent: usize,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<Vec<CoinCancelSummary>> {
+        let mut oids_by_coin: HashMap<String, Vec<u64>> = HashMap::new();
+        for order in self.info_client().open_orders(address).await? {
+            oids_by_coin.entry(order.coin).or_default().push(order.oid);
+        }
+
+        let mut summaries: Vec<CoinCancelSummary> = stream::iter(oids_by_coin)
+            .map(|(coin, oids)| async move {
+                let cancels = oids
+                    .iter()
+                    .map(|&oid| ClientCancelRequest {
+                        asset: coin.clone(),
+                        oid,
+                    })
+                    .collect();
+                let response = self.bulk_cancel(cancels, wallet).await;
+                CoinCancelSummary::from_response(coin, oids, response)
+            })
+            .buffer_unordered(max_concurrent.max(1))
+            .collect()
+            .await;
+        summaries.sort_by(|a, b| a.coin.cmp(&b.coin));
+        Ok(summaries)
+    }
+
+    pub async fn cancel_by_cloid(
+        &self,
+        cancel: ClientCancelRequestCloid,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.bulk_cancel_by_cloid(vec![cancel], wallet).await
+    }
+
+    pub async fn bulk_cancel_by_cloid(
+        &self,
+        cancels: Vec<ClientCancelRequestCloid>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let payload = self.build_cancel_by_cloid_payload(cancels, wallet)?;
+        self.post_payload(&payload).await
+    }
+
+    pub async fn bulk_cancel_by_cloid_with_nonce(
+        &self,
+        cancels: Vec<ClientCancelRequestCloid>,
+        nonce: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = self.cancel_by_cloid_action(cancels)?;
+        let payload = self.build_l1_action_payload_with_nonce(&action, nonce, wallet)?;
+        self.post_payload(&payload).await
+    }
+
+    pub fn build_cancel_by_cloid_payload(
+        &self,
+        cancels: Vec<ClientCancelRequestCloid>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangePayload> {
+        let action = self.cancel_by_cloid_action(cancels)?;
+        self.build_l1_action_payload(&action, wallet)
+    }
+
+    fn cancel_by_cloid_action(&self, cancels: Vec<ClientCancelRequestCloid>) -> Result<Actions> {
+        let mut transformed_cancels: Vec<CancelRequestCloid> = Vec::new();
+        for cancel in cancels.into_iter() {
+            let asset = self.resolve_asset(&cancel.asset)?;
+            transformed_cancels.push(CancelRequestCloid {
+                asset,
+                cloid: uuid_to_hex_string(cancel.cloid),
+            });
+        }
+
+        Ok(Actions::CancelByCloid(BulkCancelCloid {
+            cancels: transformed_cancels,
+        }))
+    }
+
+    // Leverage above the coin's max_leverage is rejected before signing
+    pub async fn update_leverage(
+        &self,
+        leverage: u32,
+        coin: &str,
+        is_cross: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.update_leverage_inner(leverage, coin, is_cross, false, wallet)
+            .await
+    }
+
+    // Like update_leverage, but caps `leverage` at the coin's max_leverage instead of erroring
+    pub async fn update_leverage_clamped(
+        &self,
+        leverage: u32,
+        coin: &str,
+        is_cross: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.update_leverage_inner(leverage, coin, is_cross, true, wallet)
+            .await
+    }
+
+    async fn update_leverage_inner(
+        &self,
+        leverage: u32,
+        coin: &str,
+        is_cross: bool,
+        clamp: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+
+        let timestamp = next_nonce();
+
+        let asset_index = self.resolve_asset(coin)?;
+        let leverage = self.checked_leverage(asset_index, coin, leverage, clamp)?;
+        let action = Actions::UpdateLeverage(UpdateLeverage {
+            asset: asset_index,
+            is_cross,
+            leverage,
         });
         let connection_id = action.hash(timestamp, self.vault_address)?;
         let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
-        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
+        let is_mainnet = self.transport.base_url() == MAINNET_API_URL;
         let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
 
         self.post(action, signature, timestamp).await
     }
 
-    pub async fn approve_agent(
-        &self,
-        wallet: Option<&LocalWallet>,
-    ) -> Result<(String, ExchangeResponseStatus)> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let key = H256::from(generate_random_key()?).encode_hex()[2..].to_string();
+    // max_leverage is 0 when meta doesn't report it, in which case only 0 is rejected
+    fn checked_leverage(
+        &self,
+        asset_index: u32,
+        coin: &str,
+        leverage: u32,
+        clamp: bool,
+    ) -> Result<u32> {
+        let max_leverage = self
+            .meta
+            .universe
+            .get(asset_index as usize)
+            .map_or(0, |asset| asset.max_leverage);
+        if leverage == 0 || (max_leverage > 0 && leverage > max_leverage && !clamp) {
+            return Err(Error::InvalidOrder(format!(
+                "leverage {leverage} is outside 1..={max_leverage} for {coin}"
+            )));
+        }
+        if max_leverage > 0 {
+            Ok(leverage.min(max_leverage))
+        } else {
+            Ok(leverage)
+        }
+    }
+
+    pub async fn update_leverage_typed(
+        &self,
+        coin: &str,
+        leverage: u32,
+        margin: MarginType,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.update_leverage(leverage, coin, margin.is_cross(), wallet)
+            .await
+    }
+
+    // Switches `coin` to `margin` at `leverage`. Returns None without signing anything when an
+    // open position already uses that mode and leverage; without a position the current
+    // setting can't be read back, so the update is always sent.
+    pub async fn set_margin_mode(
+        &self,
+        coin: &str,
+        margin: MarginType,
+        leverage: u32,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<Option<ExchangeResponseStatus>> {
+        let asset = self
+            .meta
+            .universe
+            .iter()
+            .find(|asset| asset.name == coin)
+            .ok_or(Error::AssetNotFound)?;
+        if margin.is_cross() && asset.only_isolated {
+            return Err(Error::InvalidOrder(format!(
+                "{coin} only supports isolated margin"
+            )));
+        }
+        self.checked_leverage(self.resolve_asset(coin)?, coin, leverage, false)?;
+
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        let user_state = self.info_client().user_state(address).await?;
+        let current = user_state
+            .asset_positions
+            .iter()
+            .find(|asset_position| asset_position.position.coin == coin)
+            .map(|asset_position| &asset_position.position.leverage);
+        if let Some(current) = current {
+            let current_margin = MarginType::from(current.type_string == "cross");
+            if current_margin == margin && current.value == leverage {
+                return Ok(None);
+            }
+        }
+
+        self.update_leverage_typed(coin, leverage, margin, wallet)
+            .await
+            .map(Some)
+    }
+
+    // Reads the current setting back with activeAssetData, which works without an open
+    // position too, and only signs an update when the margin mode or leverage differ.
+    // Returns whether an update was sent; a rejected update is an error
+    pub async fn ensure_leverage(
+        &self,
+        coin: &str,
+        leverage: u32,
+        margin: MarginType,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<bool> {
+        self.checked_leverage(self.resolve_asset(coin)?, coin, leverage, false)?;
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        let current = self
+            .info_client()
+            .active_asset_data(address, coin)
+            .await?
+            .leverage;
+        if MarginType::from(current.type_string == "cross") == margin && current.value == leverage {
+            return Ok(false);
+        }
+
+        match self
+            .update_leverage_typed(coin, leverage, margin, wallet)
+            .await?
+        {
+            ExchangeResponseStatus::Ok(_) => Ok(true),
+            ExchangeResponseStatus::Err(e) => Err(Error::ExchangeRejected(e)),
+        }
+    }
+
+    // Whether `coin` is a listed perp that hasn't been delisted, per the meta loaded at startup
+    pub fn is_tradable(&self, coin: &str) -> bool {
+        self.meta.asset_status(coin).is_tradable()
+    }
+
+    // Initial margin an order for `sz` `coin` at `px` would lock, at the coin's leverage and
+    // margin mode read back with activeAssetData, which reports them without a position too
+    pub async fn required_margin(
+        &self,
+        coin: &str,
+        sz: f64,
+        px: f64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<MarginRequirement> {
+        self.resolve_asset(coin)?;
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        let current = self
+            .info_client()
+            .active_asset_data(address, coin)
+            .await?
+            .leverage;
+
+        let margin = self
+            .meta
+            .initial_margin(coin, sz, px, current.value)
+            .ok_or_else(|| Error::GenericParse(format!("No leverage available for {coin}")))?;
+        Ok(MarginRequirement {
+            margin,
+            leverage: current.value,
+            margin_type: MarginType::from(current.type_string == "cross"),
+        })
+    }
+
+    /// There is no batched leverage action, so the updates are signed with distinct nonces
+    /// and sent concurrently. Results are returned in the same order as `updates`.
+    pub async fn bulk_update_leverage(
+        &self,
+        updates: Vec<(String, u32, bool)>,
+        wallet: Option<&LocalWallet>,
+    ) -> Vec<(String, Result<ExchangeResponseStatus>)> {
+        let responses = join_all(updates.iter().map(|(coin, leverage, is_cross)| {
+            self.update_leverage(*leverage, coin, *is_cross, wallet)
+        }))
+        .await;
+
+        updates
+            .into_iter()
+            .map(|(coin, _, _)| coin)
+            .zip(responses)
+            .collect()
+    }
+
+    // Margin mode is a per-asset setting sent with updateLeverage, there is no account wide
+    // default. This switches every tradable perp to `margin` at the leverage it is already
+    // set to, read with activeAssetData, and reads the setting back afterwards to confirm
+    // it. Coins already in `margin` and coins that only support isolated margin (when
+    // switching to cross) are left out of the result. At most `max_concurrent` coins are
+    // in flight at once to stay within rate limits. Results are sorted by coin.
+    pub async fn set_margin_mode_all(
+        &self,
+        margin: MarginType,
+        max_concurrent: usize,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<Vec<(String, Result<ExchangeResponseStatus>)>> {
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        let coins = self
+            .meta
+            .universe
+            .iter()
+            .filter(|asset| !asset.is_delisted)
+            .filter(|asset| !(margin.is_cross() && asset.only_isolated))
+            .map(|asset| asset.name.clone());
+
+        let results: Vec<(String, Result<Option<ExchangeResponseStatus>>)> = stream::iter(coins)
+            .map(|coin| async move {
+                let result = self
+                    .switch_margin_mode(address, &coin, margin, wallet)
+                    .await;
+                (coin, result)
+            })
+            .buffer_unordered(max_concurrent.max(1))
+            .collect()
+            .await;
+        let mut results: Vec<(String, Result<ExchangeResponseStatus>)> = results
+            .into_iter()
+            .filter_map(|(coin, result)| result.transpose().map(|result| (coin, result)))
+            .collect();
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(results)
+    }
+
+    // None when `coin` is already in `margin`. A setting that reads back unchanged after an
+    // accepted update is an error
+    async fn switch_margin_mode(
+        &self,
+        address: H160,
+        coin: &str,
+        margin: MarginType,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<Option<ExchangeResponseStatus>> {
+        let info_client = self.info_client();
+        let current = info_client.active_asset_data(address, coin).await?.leverage;
+        if MarginType::from(current.type_string == "cross") == margin {
+            return Ok(None);
+        }
+
+        let response = self
+            .update_leverage_typed(coin, current.value, margin, wallet)
+            .await?;
+        if let ExchangeResponseStatus::Err(_) = response {
+            return Ok(Some(response));
+        }
+        let updated = info_client.active_asset_data(address, coin).await?.leverage;
+        if MarginType::from(updated.type_string == "cross") != margin
+            || updated.value != current.value
+        {
+            return Err(Error::GenericRequest(format!(
+                "{coin} reads back as {} {}x after the margin mode update",
+                updated.type_string, updated.value
+            )));
+        }
+        Ok(Some(response))
+    }
+
+    pub async fn update_isolated_margin(
+        &self,
+        amount: f64,
+        coin: &str,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+
+        let amount = (amount * 1_000_000.0).round() as i64;
+        let timestamp = next_nonce();
+
+        let asset_index = self.resolve_asset(coin)?;
+        let action = Actions::UpdateIsolatedMargin(UpdateIsolatedMargin {
+            asset: asset_index,
+            is_buy: true,
+            ntli: amount,
+        });
+        let connection_id = action.hash(timestamp, self.vault_address)?;
+        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let is_mainnet = self.transport.base_url() == MAINNET_API_URL;
+        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+
+        self.post(action, signature, timestamp).await
+    }
+
+    pub async fn approve_agent(
+        &self,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<(String, ExchangeResponseStatus)> {
+        self.approve_agent_with_key(generate_random_key()?, wallet)
+            .await
+    }
+
+    // Approves the agent with private key `key` instead of a random one, so the action and
+    // signature are reproducible (see generate_key_from_seed)
+    pub async fn approve_agent_with_key(
+        &self,
+        key: [u8; 32],
+        wallet: Option<&LocalWallet>,
+    ) -> Result<(String, ExchangeResponseStatus)> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        let key = H256::from(key).encode_hex()[2..].to_string();
+
+        let address = agent_address_from_key(&key)?;
+        let connection_id = keccak(address);
+
+        let (chain, l1_name) = if self.transport.base_url().eq(MAINNET_API_URL) {
+            (EthChain::Arbitrum, "Arbitrum".to_string())
+        } else {
+            (EthChain::ArbitrumGoerli, "ArbitrumGoerli".to_string())
+        };
+
+        let source = "https://hyperliquid.xyz".to_string();
+        let action = serde_json::to_value(Actions::Connect(AgentConnect {
+            chain: l1_name,
+            agent: Agent {
+                source: source.clone(),
+                connection_id,
+            },
+            agent_address: address,
+        }))
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let signature = sign_with_agent(wallet, chain, &source, connection_id)?;
+        let timestamp = next_nonce();
+        Ok((key, self.post(action, signature, timestamp).await?))
+    }
+
+    pub async fn claim_rewards(
+        &self,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        let referral = self.info_client().referral(address).await?;
+        let unclaimed_rewards: f64 = referral
+            .unclaimed_rewards
+            .parse()
+            .map_err(|_| Error::FloatStringParse)?;
+        if unclaimed_rewards < EPSILON {
+            return Err(Error::NoRewardsToClaim);
+        }
+
+        let action = Actions::ClaimRewards;
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    // An empty name clears the display name
+    pub async fn set_display_name(
+        &self,
+        name: &str,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SetDisplayName(SetDisplayName {
+            display_name: name.to_string(),
+        });
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    // Attaches the account to the referrer owning `code`. The exchange only accepts this once,
+    // and only before the account's first trades
+    pub async fn set_referrer(
+        &self,
+        code: &str,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SetReferrer(SetReferrer {
+            code: code.to_string(),
+        });
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    // Dead man's switch: cancels every open order at `time` (in milliseconds, at least 5
+    // seconds ahead) unless it is moved again before then. None clears it. The exchange
+    // limits how many times a day it can be triggered
+    pub async fn schedule_cancel(
+        &self,
+        time: Option<u64>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::ScheduleCancel(ScheduleCancel { time });
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    // Brings every coin in `config` to its leverage and margin mode, skipping the ones that
+    // already match, then arms the scheduled cancel. Failures are collected in the report
+    // instead of stopping the rest. Safe to rerun: the next call only retries what is still off
+    pub async fn apply_config(
+        &self,
+        config: AccountConfig,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<AccountConfigReport> {
+        let results = join_all(config.coins.iter().map(|coin_config| {
+            self.ensure_leverage(
+                &coin_config.coin,
+                coin_config.leverage,
+                coin_config.margin,
+                wallet,
+            )
+        }))
+        .await;
+
+        let mut report = AccountConfigReport::default();
+        for (coin_config, result) in config.coins.into_iter().zip(results) {
+            match result {
+                Ok(true) => report.updated.push(coin_config.coin),
+                Ok(false) => report.unchanged.push(coin_config.coin),
+                Err(e) => report.failed.push((coin_config.coin, e)),
+            }
+        }
+
+        if let Some(after) = config.schedule_cancel_after {
+            let time = now_timestamp_ms() + after.as_millis() as u64;
+            match self.schedule_cancel(Some(time), wallet).await {
+                Ok(ExchangeResponseStatus::Ok(_)) => report.schedule_cancel_time = Some(time),
+                Ok(ExchangeResponseStatus::Err(e)) => {
+                    report.schedule_cancel_error = Some(Error::ExchangeRejected(e))
+                }
+                Err(e) => report.schedule_cancel_error = Some(e),
+            }
+        }
+        Ok(report)
+    }
+
+    // Buys `weight` extra units of rate limit headroom for the account
+    pub async fn reserve_request_weight(
+        &self,
+        weight: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::ReserveRequestWeight(ReserveRequestWeight { weight });
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    // Routes the user's HyperEVM transactions to big blocks (higher gas limit, slower) or
+    // back to small blocks
+    pub async fn use_big_blocks(
+        &self,
+        enable: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::EvmUserModify(EvmUserModify {
+            using_big_blocks: enable,
+        });
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    async fn spot_deploy(
+        &self,
+        spot_deploy: SpotDeploy,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SpotDeploy(spot_deploy);
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    pub async fn spot_deploy_register_token(
+        &self,
+        name: &str,
+        sz_decimals: u32,
+        wei_decimals: u32,
+        max_gas: u64,
+        full_name: Option<&str>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let register_token = RegisterToken {
+            spec: TokenSpec {
+                name: name.to_string(),
+                sz_decimals,
+                wei_decimals,
+            },
+            max_gas,
+            full_name: full_name.map(ToString::to_string),
+        };
+        self.spot_deploy(SpotDeploy::RegisterToken(register_token), wallet)
+            .await
+    }
+
+    // Amounts are in wei of the token being deployed, see f64_to_token_amount
+    pub async fn spot_deploy_user_genesis(
+        &self,
+        token: u32,
+        user_and_wei: Vec<(H160, String)>,
+        existing_token_and_wei: Vec<(u32, String)>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let user_genesis = UserGenesis {
+            token,
+            user_and_wei,
+            existing_token_and_wei,
+        };
+        self.spot_deploy(SpotDeploy::UserGenesis(user_genesis), wallet)
+            .await
+    }
+
+    pub async fn spot_deploy_genesis(
+        &self,
+        token: u32,
+        max_supply: &str,
+        no_hyperliquidity: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let genesis = Genesis {
+            token,
+            max_supply: max_supply.to_string(),
+            no_hyperliquidity: no_hyperliquidity.then_some(true),
+        };
+        self.spot_deploy(SpotDeploy::Genesis(genesis), wallet).await
+    }
+
+    pub async fn spot_deploy_register_spot(
+        &self,
+        base_token: u32,
+        quote_token: u32,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let register_spot = RegisterSpot {
+            tokens: [base_token, quote_token],
+        };
+        self.spot_deploy(SpotDeploy::RegisterSpot(register_spot), wallet)
+            .await
+    }
+
+    pub async fn spot_deploy_register_hyperliquidity(
+        &self,
+        spot: u32,
+        start_px: &str,
+        order_sz: &str,
+        n_orders: u32,
+        n_seeded_levels: Option<u32>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let register_hyperliquidity = RegisterHyperliquidity {
+            spot,
+            start_px: start_px.to_string(),
+            order_sz: order_sz.to_string(),
+            n_orders,
+            n_seeded_levels,
+        };
+        self.spot_deploy(
+            SpotDeploy::RegisterHyperliquidity(register_hyperliquidity),
+            wallet,
+        )
+        .await
+    }
+
+    // `share` is a percentage string such as "100%"
+    pub async fn spot_deploy_set_deployer_trading_fee_share(
+        &self,
+        token: u32,
+        share: &str,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let fee_share = SetDeployerTradingFeeShare {
+            token,
+            share: share.to_string(),
+        };
+        self.spot_deploy(SpotDeploy::SetDeployerTradingFeeShare(fee_share), wallet)
+            .await
+    }
+
+    async fn perp_deploy(
+        &self,
+        perp_deploy: PerpDeploy,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::PerpDeploy(perp_deploy);
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    // `schema` must be set when registering the first asset of a new dex
+    pub async fn perp_deploy_register_asset(
+        &self,
+        dex: &str,
+        asset_request: PerpAssetRequest,
+        max_gas: Option<u64>,
+        schema: Option<PerpDexSchema>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let register_asset = RegisterAsset {
+            max_gas,
+            asset_request,
+            dex: dex.to_string(),
+            schema,
+        };
+        self.perp_deploy(PerpDeploy::RegisterAsset(register_asset), wallet)
+            .await
+    }
+
+    // Prices are (coin, px) pairs; the exchange expects them sorted by coin
+    pub async fn perp_deploy_set_oracle(
+        &self,
+        dex: &str,
+        mut oracle_pxs: Vec<(String, String)>,
+        mut mark_pxs: Vec<Vec<(String, String)>>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        oracle_pxs.sort();
+        for pxs in &mut mark_pxs {
+            pxs.sort();
+        }
+        let set_oracle = SetOracle {
+            dex: dex.to_string(),
+            oracle_pxs,
+            mark_pxs,
+        };
+        self.perp_deploy(PerpDeploy::SetOracle(set_oracle), wallet)
+            .await
+    }
+
+    async fn c_signer_action(
+        &self,
+        c_signer_action: CSignerAction,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::CSignerAction(c_signer_action);
+        self.post_payload(&self.build_l1_action_payload(&action, wallet)?)
+            .await
+    }
+
+    // Signed by the validator's signer key
+    pub async fn jail_self(&self, wallet: Option<&LocalWallet>) -> Result<ExchangeResponseStatus> {
+        self.c_signer_action(CSignerAction::JailSelf(()), wallet)
+            .await
+    }
+
+    pub async fn unjail_self(
+        &self,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.c_signer_action(CSignerAction::UnjailSelf(()), wallet)
+            .await
+    }
+
+    // `wei` is the HYPE amount in its smallest unit (8 decimals)
+    pub async fn token_delegate(
+        &self,
+        validator: H160,
+        wei: u64,
+        is_undelegate: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        let timestamp = next_nonce();
+
+        let (hyperliquid_chain, signature_chain_id) = self.user_signed_chain();
+        let token_delegate = TokenDelegate {
+            signature_chain_id,
+            hyperliquid_chain,
+            validator,
+            wei,
+            is_undelegate,
+            nonce: timestamp,
+        };
+        let signature = sign_typed_data(&token_delegate, wallet)?;
+        let action = serde_json::to_value(Actions::TokenDelegate(token_delegate))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        // user signed actions can't be sent on behalf of a vault
+        self.post_with_vault(action, signature, timestamp, None)
+            .await
+    }
+
+    // Crosses the book with an IOC order priced `slippage` (default 5%) away from the mid
+    pub async fn market_open(
+        &self,
+        coin: &str,
+        side: Side,
+        sz: f64,
+        slippage: Option<f64>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<MarketOrderFill> {
+        let sz_decimals = self
+            .meta
+            .universe
+            .iter()
+            .find(|asset| asset.name == coin)
+            .ok_or(Error::AssetNotFound)?
+            .sz_decimals;
+        let mid: f64 = self
+            .info_client()
+            .all_mids()
+            .await?
+            .get(coin)
+            .ok_or(Error::AssetNotFound)?
+            .parse()
+            .map_err(|_| Error::FloatStringParse)?;
+        let limit_px = slippage_price(mid, side, slippage.unwrap_or(DEFAULT_SLIPPAGE), sz_decimals);
+
+        let order = ClientOrderRequest {
+            asset: coin.to_string(),
+            side,
+            reduce_only: false,
+            limit_px,
+            sz,
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Ioc".to_string(),
+            }),
+        };
+        let response = self.order(order, wallet).await?;
+        MarketOrderFill::from_response(response, sz)
+    }
+
+    // Spends `pct` percent (0-100] of the withdrawable balance as order notional, without
+    // applying leverage, and places a Gtc limit order at `limit_px`
+    pub async fn order_by_notional_pct(
+        &self,
+        coin: &str,
+        side: Side,
+        pct: f64,
+        limit_px: f64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        if !(pct > 0.0 && pct <= 100.0) {
+            return Err(Error::InvalidOrder(format!(
+                "pct must be in (0, 100], got {pct}"
+            )));
+        }
+        if !limit_px.is_finite() || limit_px <= 0.0 {
+            return Err(Error::InvalidOrder(format!(
+                "limit_px must be a positive number, got {limit_px}"
+            )));
+        }
+        let sz_decimals = self
+            .meta
+            .universe
+            .iter()
+            .find(|asset| asset.name == coin)
+            .ok_or(Error::AssetNotFound)?
+            .sz_decimals;
+
+        let address = self
+            .vault_address
+            .unwrap_or(wallet.unwrap_or(&self.wallet).address());
+        let user_state = self.info_client().user_state(address).await?;
+        let withdrawable: f64 = user_state
+            .withdrawable
+            .parse()
+            .map_err(|_| Error::FloatStringParse)?;
+
+        let notional = withdrawable * pct / 100.0;
+        let sz = truncate_float(notional / limit_px, sz_decimals, false);
+        if sz <= 0.0 {
+            return Err(Error::InvalidOrder(format!(
+                "{pct}% of {withdrawable} withdrawable at {limit_px} rounds to a size of zero \
+                 with {sz_decimals} size decimals for {coin}"
+            )));
+        }
+
+        let order = ClientOrderRequest {
+            asset: coin.to_string(),
+            side,
+            reduce_only: false,
+            limit_px,
+            sz,
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Gtc".to_string(),
+            }),
+        };
+        self.order(order, wallet).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use super::*;
+    use crate::{
+        exchange::order::{Limit, OrderRequest, Trigger},
+        generate_key_from_seed, ClientLimit, ClientOrder, MockTransport, Order, Side,
+    };
+    use ethers::types::transaction::eip712::{Eip712, TypedData};
+    use serde_json::json;
+
+    fn get_wallet() -> Result<LocalWallet> {
+        let priv_key = "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e";
+        priv_key
+            .parse::<LocalWallet>()
+            .map_err(|e| Error::Wallet(e.to_string()))
+    }
+
+    fn eth_meta() -> serde_json::Value {
+        json!({ "universe": [{ "name": "ETH", "szDecimals": 4 }] })
+    }
+
+    // Add the other responses a test needs with `with_response`
+    fn eth_transport() -> MockTransport {
+        MockTransport::new(BaseUrl::Localhost).with_response("meta", eth_meta())
+    }
+
+    fn eth_bid(limit_px: f64) -> ClientOrderRequest {
+        ClientOrderRequest {
+            asset: "ETH".to_string(),
+            side: Side::Bid,
+            reduce_only: false,
+            limit_px,
+            sz: 0.01,
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Gtc".to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_limit_order_action_hashing() -> Result<()> {
+        let wallet = get_wallet()?;
+        let action = Actions::Order(BulkOrder {
+            orders: vec![OrderRequest {
+                asset: 1,
+                is_buy: true,
+                limit_px: "2000.0".to_string(),
+                sz: "3.5".to_string(),
+                reduce_only: false,
+                order_type: Order::Limit(Limit {
+                    tif: "Ioc".to_string(),
+                }),
+                cloid: None,
+            }],
+            grouping: Grouping::Na,
+            builder: None,
+        });
+        let connection_id = action.hash(1583838, None)?;
+
+        let signature = sign_l1_action(&wallet, connection_id, true)?;
+        assert_eq!(signature.to_string(), "77957e58e70f43b6b68581f2dc42011fc384538a2e5b7bf42d5b936f19fbb67360721a8598727230f67080efee48c812a6a4442013fd3b0eed509171bef9f23f1c");
+
+        let signature = sign_l1_action(&wallet, connection_id, false)?;
+        assert_eq!(signature.to_string(), "cd0925372ff1ed499e54883e9a6205ecfadec748f80ec463fe2f84f1209648776377961965cb7b12414186b1ea291e95fd512722427efcbcfb3b0b2bcd4d79d01c");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_limit_order_action_hashing_with_cloid() -> Result<()> {
+        let cloid = uuid::Uuid::from_str("1e60610f-0b3d-4205-97c8-8c1fed2ad5ee")
+            .map_err(|_e| uuid::Uuid::new_v4());
+        let wallet = get_wallet()?;
+        let action = Actions::Order(BulkOrder {
+            orders: vec![OrderRequest {
+                asset: 1,
+                is_buy: true,
+                limit_px: "2000.0".to_string(),
+                sz: "3.5".to_string(),
+                reduce_only: false,
+                order_type: Order::Limit(Limit {
+                    tif: "Ioc".to_string(),
+                }),
+                cloid: Some(uuid_to_hex_string(cloid.unwrap())),
+            }],
+            grouping: Grouping::Na,
+            builder: None,
+        });
+        let connection_id = action.hash(1583838, None)?;
+
+        let signature = sign_l1_action(&wallet, connection_id, true)?;
+        assert_eq!(signature.to_string(), "d3e894092eb27098077145714630a77bbe3836120ee29df7d935d8510b03a08f456de5ec1be82aa65fc6ecda9ef928b0445e212517a98858cfaa251c4cd7552b1c");
+
+        let signature = sign_l1_action(&wallet, connection_id, false)?;
+        assert_eq!(signature.to_string(), "3768349dbb22a7fd770fc9fc50c7b5124a7da342ea579b309f58002ceae49b4357badc7909770919c45d850aabb08474ff2b7b3204ae5b66d9f7375582981f111c");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tpsl_order_action_hashing() -> Result<()> {
+        for (tpsl, mainnet_signature, testnet_signature) in [
+            (
+                "tp",
+                "e844cafedb695abbc28b3178b136d262327a72bba1012152f3b5b675147e98312d42de83976b05becf768ad882f6f6a1bfa65afadc71f945c2a98473317097ee1b",
+                "f360f6173c1d9a8ff2d8677e1fc4cb787122542985129c42e8bce47c5d58f6910ee42b10fd69af0bff0dd484e2cb8d3fa8fecfec13bde5e31f5d3d47d1e5a73f1b"
+            ),
+            (
+                "sl",
+                "d10f92a81428c0b57fb619f206bca34ad0cb668be8305306804b27491b4f9c257a87dbd87ad5b6e2bce2ae466b004f7572c5080672ed58cdcb3ffaedcd9de9111c",
+                "51b70df3ee8afcdf192390ee79a18b54a8ec92c86653e8ef80b0c90a7cf9850500c6653c4aa2317e7312dfc9b2aeba515d801d7e8af66567539861a6d5eb2d2b1c"
+            )
+        ] {
+            let wallet = get_wallet()?;
+            let action = Actions::Order(BulkOrder {
+                orders: vec![
+                    OrderRequest {
+                        asset: 1,
+                        is_buy: true,
+                        limit_px: "2000.0".to_string(),
+                        sz: "3.5".to_string(),
+                        reduce_only: false,
+                        order_type: Order::Trigger(Trigger {
+                            trigger_px: "2000.0".to_string(),
+                            is_market: true,
+                            tpsl: tpsl.to_string(),
+                        }),
+                        cloid: None,
+                    }
+                ],
+                grouping: Grouping::Na,
+                builder: None,
+            });
+            let connection_id = action.hash(1583838, None)?;
+
+            let signature = sign_l1_action(&wallet, connection_id, true)?;
+            assert_eq!(signature.to_string(), mainnet_signature);
+
+            let signature = sign_l1_action(&wallet, connection_id, false)?;
+            assert_eq!(signature.to_string(), testnet_signature);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancel_action_hashing() -> Result<()> {
+        let wallet = get_wallet()?;
+        let action = Actions::Cancel(BulkCancel {
+            cancels: vec![CancelRequest {
+                asset: 1,
+                oid: 82382,
+            }],
+        });
+        let connection_id = action.hash(1583838, None)?;
+
+        let signature = sign_l1_action(&wallet, connection_id, true)?;
+        assert_eq!(signature.to_string(), "02f76cc5b16e0810152fa0e14e7b219f49c361e3325f771544c6f54e157bf9fa17ed0afc11a98596be85d5cd9f86600aad515337318f7ab346e5ccc1b03425d51b");
+
+        let signature = sign_l1_action(&wallet, connection_id, false)?;
+        assert_eq!(signature.to_string(), "6ffebadfd48067663390962539fbde76cfa36f53be65abe2ab72c9db6d0db44457720db9d7c4860f142a484f070c84eb4b9694c3a617c83f0d698a27e55fd5e01c");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_orders_from_shared_client() -> Result<()> {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ExchangeClient>();
+
+        let transport = Arc::new(eth_transport().with_response(
+            "order",
+            json!({
+                "status": "ok",
+                "response": {
+                    "type": "order",
+                    "data": { "statuses": [{ "resting": { "oid": 1 } }] }
+                }
+            }),
+        ));
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let exchange_client = Arc::new(exchange_client);
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let exchange_client = Arc::clone(&exchange_client);
+                tokio::spawn(async move {
+                    let order = eth_bid(1800.0);
+                    exchange_client.order(order, None).await
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let response = handle
+                .await
+                .map_err(|e| Error::GenericRequest(e.to_string()))?;
+            assert!(matches!(response, Ok(ExchangeResponseStatus::Ok(_))));
+        }
+
+        let mut nonces: Vec<u64> = transport
+            .requests()
+            .iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .filter_map(|(_, body)| body["nonce"].as_u64())
+            .collect();
+        nonces.sort_unstable();
+        nonces.dedup();
+        assert_eq!(nonces.len(), 8);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_asset_index_and_name_round_trip() -> Result<()> {
+        let transport = Arc::new(MockTransport::new(BaseUrl::Localhost).with_response(
+            "meta",
+            json!({ "universe": [
+                { "name": "BTC", "szDecimals": 5 },
+                { "name": "ETH", "szDecimals": 4 }
+            ] }),
+        ));
+        let exchange_client =
+            ExchangeClient::with_transport(transport, get_wallet()?, None, None).await?;
+
+        assert_eq!(exchange_client.asset_index("ETH"), Some(1));
+        assert_eq!(exchange_client.asset_name(0), Some("BTC"));
+        assert_eq!(exchange_client.asset_index("DOGE"), None);
+        assert_eq!(exchange_client.asset_name(2), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_meta_reports_reordered_coins() -> Result<()> {
+        let transport = Arc::new(MockTransport::new(BaseUrl::Localhost).with_response(
+            "meta",
+            json!({ "universe": [
+                { "name": "ETH", "szDecimals": 4 },
+                { "name": "BTC", "szDecimals": 5 },
+                { "name": "SOL", "szDecimals": 2 },
+                { "name": "HYPE", "szDecimals": 2 }
+            ] }),
+        ));
+        let cached_meta = serde_json::from_value(json!({ "universe": [
+            { "name": "BTC", "szDecimals": 5 },
+            { "name": "ETH", "szDecimals": 4 },
+            { "name": "SOL", "szDecimals": 2 },
+            { "name": "DOGE", "szDecimals": 0 }
+        ] }))
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let exchange_client = ExchangeClient::with_transport(
+            transport.clone(),
+            get_wallet()?,
+            Some(cached_meta),
+            None,
+        )
+        .await?;
+        assert_eq!(
+            exchange_client.verify_meta().await?,
+            vec!["BTC", "DOGE", "ETH", "HYPE"]
+        );
+
+        let current = ExchangeClient::with_transport(transport, get_wallet()?, None, None).await?;
+        assert!(current.verify_meta().await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_order_by_notional_pct_sizes_from_withdrawable() -> Result<()> {
+        let user_state = |withdrawable: &str| {
+            let summary = json!({
+                "accountValue": "0.0",
+                "totalMarginUsed": "0.0",
+                "totalNtlPos": "0.0",
+                "totalRawUsd": "0.0"
+            });
+            json!({
+                "assetPositions": [],
+                "crossMarginSummary": summary,
+                "marginSummary": summary,
+                "withdrawable": withdrawable
+            })
+        };
+        let transport = Arc::new(
+            eth_transport()
+                .with_response("clearinghouseState", user_state("100.0"))
+                .with_response(
+                    "order",
+                    json!({
+                        "status": "ok",
+                        "response": {
+                            "type": "order",
+                            "data": { "statuses": [{ "resting": { "oid": 1 } }] }
+                        }
+                    }),
+                ),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        exchange_client
+            .order_by_notional_pct("ETH", Side::Bid, 10.0, 1800.0, None)
+            .await?;
+        let (_, body) = transport
+            .requests()
+            .into_iter()
+            .rfind(|(url_path, _)| url_path == "/exchange")
+            .ok_or(Error::GenericRequest("no order sent".to_string()))?;
+        // 10 / 1800 = 0.00555.. truncated to 4 decimals
+        assert_eq!(body["action"]["orders"][0]["s"], "0.0055");
+
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response("clearinghouseState", user_state("1.0")),
+        );
+        let exchange_client = ExchangeClient::with_transport(
+            transport,
+            get_wallet()?,
+            Some(exchange_client.meta.clone()),
+            None,
+        )
+        .await?;
+        assert!(matches!(
+            exchange_client
+                .order_by_notional_pct("ETH", Side::Bid, 1.0, 1800.0, None)
+                .await,
+            Err(Error::InvalidOrder(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_display_name_action_format() -> Result<()> {
+        let action = Actions::SetDisplayName(SetDisplayName {
+            display_name: "alice".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            json!({ "type": "setDisplayName", "displayName": "alice" })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_referrer_action_format() -> Result<()> {
+        let action = Actions::SetReferrer(SetReferrer {
+            code: "ALICE".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            json!({ "type": "setReferrer", "code": "ALICE" })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserve_request_weight_action_hash() -> Result<()> {
+        let action = Actions::ReserveRequestWeight(ReserveRequestWeight { weight: 1000 });
+        assert_eq!(
+            serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            json!({ "type": "reserveRequestWeight", "weight": 1000 })
+        );
+
+        let other_weight = Actions::ReserveRequestWeight(ReserveRequestWeight { weight: 1001 });
+        assert_ne!(
+            action.hash(1583838, None)?,
+            other_weight.hash(1583838, None)?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_use_big_blocks_action_format() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "evmUserModify",
+            json!({ "status": "ok", "response": { "type": "default" } }),
+        ));
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        let response = exchange_client.use_big_blocks(true, None).await?;
+        assert!(matches!(response, ExchangeResponseStatus::Ok(_)));
+        let (_, body) = transport
+            .requests()
+            .into_iter()
+            .rfind(|(url_path, _)| url_path == "/exchange")
+            .ok_or(Error::GenericRequest("no action sent".to_string()))?;
+        assert_eq!(
+            body["action"],
+            json!({ "type": "evmUserModify", "usingBigBlocks": true })
+        );
+
+        let action: Actions = serde_json::from_value(body["action"].clone())
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let nonce = body["nonce"].as_u64().unwrap_or_default();
+        let signature = sign_action(&action, &get_wallet()?, nonce, None, None, false)?;
+        assert_eq!(
+            body["signature"],
+            serde_json::to_value(signature).map_err(|e| Error::JsonParse(e.to_string()))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_delegate_signing_hash() -> Result<()> {
+        let token_delegate = TokenDelegate {
+            signature_chain_id: U256::from(421614),
+            hyperliquid_chain: "Testnet".to_string(),
+            validator: H160::from_str("0x5ac99df645f3414876c816caa18b2d234024b487")
+                .map_err(|e| Error::GenericParse(e.to_string()))?,
+            wei: 100000000,
+            is_undelegate: false,
+            nonce: 1733961600000,
+        };
+
+        let action = serde_json::to_value(Actions::TokenDelegate(token_delegate.clone()))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(
+            action,
+            json!({
+                "type": "tokenDelegate",
+                "signatureChainId": "0x66eee",
+                "hyperliquidChain": "Testnet",
+                "validator": "0x5ac99df645f3414876c816caa18b2d234024b487",
+                "wei": 100000000,
+                "isUndelegate": false,
+                "nonce": 1733961600000u64
+            })
+        );
+
+        // the same message described as eth_signTypedData json must hash identically
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "domain": {
+                "name": "HyperliquidSignTransaction",
+                "version": "1",
+                "chainId": 421614,
+                "verifyingContract": "0x0000000000000000000000000000000000000000"
+            },
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "HyperliquidTransaction:TokenDelegate": [
+                    { "name": "hyperliquidChain", "type": "string" },
+                    { "name": "validator", "type": "address" },
+                    { "name": "wei", "type": "uint64" },
+                    { "name": "isUndelegate", "type": "bool" },
+                    { "name": "nonce", "type": "uint64" }
+                ]
+            },
+            "primaryType": "HyperliquidTransaction:TokenDelegate",
+            "message": {
+                "hyperliquidChain": "Testnet",
+                "validator": "0x5ac99df645f3414876c816caa18b2d234024b487",
+                "wei": 100000000,
+                "isUndelegate": false,
+                "nonce": 1733961600000u64
+            }
+        }))
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(
+            token_delegate
+                .encode_eip712()
+                .map_err(|e| Error::Eip712(e.to_string()))?,
+            typed_data
+                .encode_eip712()
+                .map_err(|e| Error::Eip712(e.to_string()))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_spot_deploy_action_format() -> Result<()> {
+        let action = Actions::SpotDeploy(SpotDeploy::Genesis(Genesis {
+            token: 1,
+            max_supply: "1000000000000".to_string(),
+            no_hyperliquidity: None,
+        }));
+        assert_eq!(
+            serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            json!({
+                "type": "spotDeploy",
+                "genesis": { "token": 1, "maxSupply": "1000000000000" }
+            })
+        );
+
+        let action = Actions::SpotDeploy(SpotDeploy::RegisterToken(RegisterToken {
+            spec: TokenSpec {
+                name: "TEST".to_string(),
+                sz_decimals: 2,
+                wei_decimals: 8,
+            },
+            max_gas: 1000000,
+            full_name: None,
+        }));
+        assert_eq!(
+            serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            json!({
+                "type": "spotDeploy",
+                "registerToken2": {
+                    "spec": { "name": "TEST", "szDecimals": 2, "weiDecimals": 8 },
+                    "maxGas": 1000000,
+                    "fullName": null
+                }
+            })
+        );
+        action.hash(1, None)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_debug_rejected_payloads() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "evmUserModify",
+            json!({ "status": "err", "response": "User or API Wallet does not exist" }),
+        ));
+        let mut exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        let response = exchange_client.use_big_blocks(true, None).await?;
+        assert!(matches!(response, ExchangeResponseStatus::Err(_)));
+
+        exchange_client.debug_rejected_payloads = true;
+        let Err(Error::RejectedPayload { error, payload }) =
+            exchange_client.use_big_blocks(true, None).await
+        else {
+            return Err(Error::GenericRequest(
+                "expected a rejected payload".to_string(),
+            ));
+        };
+        assert_eq!(error, "User or API Wallet does not exist");
+        let (_, body) = transport
+            .requests()
+            .into_iter()
+            .rfind(|(url_path, _)| url_path == "/exchange")
+            .ok_or(Error::GenericRequest("no action sent".to_string()))?;
+        let payload: serde_json::Value =
+            serde_json::from_str(&payload).map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(payload, body);
+        Ok(())
+    }
+
+    #[test]
+    fn test_c_signer_action_format() -> Result<()> {
+        let action = Actions::CSignerAction(CSignerAction::JailSelf(()));
+        assert_eq!(
+            serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            json!({ "type": "CSignerAction", "jailSelf": null })
+        );
+        let action = Actions::CSignerAction(CSignerAction::UnjailSelf(()));
+        assert_eq!(
+            serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            json!({ "type": "CSignerAction", "unjailSelf": null })
+        );
+        action.hash(1, None)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_perp_deploy_action_format() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "perpDeploy",
+            json!({ "status": "ok", "response": { "type": "default" } }),
+        ));
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        exchange_client
+            .perp_deploy_register_asset(
+                "test",
+                PerpAssetRequest {
+                    coin: "test:ABC".to_string(),
+                    sz_decimals: 2,
+                    oracle_px: "10.0".to_string(),
+                    margin_table_id: 10,
+                    only_isolated: false,
+                },
+                None,
+                Some(PerpDexSchema {
+                    full_name: "Test Dex".to_string(),
+                    collateral_token: 0,
+                    oracle_updater: None,
+                }),
+                None,
+            )
+            .await?;
+        exchange_client
+            .perp_deploy_set_oracle(
+                "test",
+                vec![
+                    ("test:XYZ".to_string(), "2.0".to_string()),
+                    ("test:ABC".to_string(), "10.0".to_string()),
+                ],
+                vec![vec![("test:ABC".to_string(), "10.1".to_string())]],
+                None,
+            )
+            .await?;
+
+        let requests: Vec<_> = transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .collect();
+        assert_eq!(
+            requests[0].1["action"],
+            json!({
+                "type": "perpDeploy",
+                "registerAsset": {
+                    "maxGas": null,
+                    "assetRequest": {
+                        "coin": "test:ABC",
+                        "szDecimals": 2,
+                        "oraclePx": "10.0",
+                        "marginTableId": 10,
+                        "onlyIsolated": false
+                    },
+                    "dex": "test",
+                    "schema": {
+                        "fullName": "Test Dex",
+                        "collateralToken": 0,
+                        "oracleUpdater": null
+                    }
+                }
+            })
+        );
+        assert_eq!(
+            requests[1].1["action"],
+            json!({
+                "type": "perpDeploy",
+                "setOracle": {
+                    "dex": "test",
+                    "oraclePxs": [["test:ABC", "10.0"], ["test:XYZ", "2.0"]],
+                    "markPxs": [[["test:ABC", "10.1"]]]
+                }
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_payload_built_off_runtime_is_posted_unchanged() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "cancel",
+            json!({
+                "status": "ok",
+                "response": { "type": "cancel", "data": { "statuses": ["success"] } }
+            }),
+        ));
+        let exchange_client = Arc::new(
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?,
+        );
+
+        let builder = Arc::clone(&exchange_client);
+        let payload = std::thread::spawn(move || {
+            builder.build_cancel_payload(
+                vec![ClientCancelRequest {
+                    asset: "ETH".to_string(),
+                    oid: 82382,
+                }],
+                None,
+            )
+        })
+        .join()
+        .map_err(|_| Error::GenericRequest("payload thread panicked".to_string()))??;
+
+        let action: Actions = serde_json::from_value(payload.action.clone())
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let signature = sign_action(&action, &get_wallet()?, payload.nonce, None, None, false)?;
+        assert_eq!(signature, payload.signature);
+
+        exchange_client.post_payload(&payload).await?;
+        let (_, body) = transport
+            .requests()
+            .into_iter()
+            .rfind(|(url_path, _)| url_path == "/exchange")
+            .ok_or(Error::GenericRequest("no cancel sent".to_string()))?;
+        assert_eq!(
+            body,
+            serde_json::to_value(&payload).map_err(|e| Error::JsonParse(e.to_string()))?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_spot_pair_names() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "spotMeta",
+            json!({
+                "tokens": [
+                    { "name": "USDC", "szDecimals": 8, "weiDecimals": 8, "index": 0,
+                      "tokenId": "0x6d1e7cde53ba9467b783cb7c530ce054", "isCanonical": true },
+                    { "name": "PURR", "szDecimals": 0, "weiDecimals": 5, "index": 1,
+                      "tokenId": "0xc1fb593aeffbeb02f85e0308e9956a90", "isCanonical": true },
+                    { "name": "HFUN", "szDecimals": 2, "weiDecimals": 8, "index": 2,
+                      "tokenId": "0xbaf265ef389da684513d98d68edf4eae", "isCanonical": false },
+                    { "name": "Foo", "szDecimals": 2, "weiDecimals": 8, "index": 3,
+                      "tokenId": "0x01", "isCanonical": false },
+                    { "name": "FOO", "szDecimals": 2, "weiDecimals": 8, "index": 4,
+                      "tokenId": "0x02", "isCanonical": false }
+                ],
+                "universe": [
+                    { "name": "PURR/USDC", "tokens": [1, 0], "index": 0, "isCanonical": true },
+                    { "name": "@1", "tokens": [2, 0], "index": 1, "isCanonical": false },
+                    { "name": "@2", "tokens": [3, 0], "index": 2, "isCanonical": false },
+                    { "name": "@3", "tokens": [4, 0], "index": 3, "isCanonical": false }
+                ]
+            }),
+        ));
+        let mut exchange_client =
+            ExchangeClient::with_transport(transport, get_wallet()?, None, None).await?;
+        exchange_client.load_spot_meta().await?;
+
+        assert_eq!(exchange_client.resolve_asset("ETH")?, 0);
+        assert_eq!(exchange_client.resolve_asset("PURR/USDC")?, 10000);
+        assert_eq!(exchange_client.resolve_asset("purr/usdc")?, 10000);
+        assert_eq!(exchange_client.resolve_asset("@1")?, 10001);
+        assert_eq!(exchange_client.resolve_asset("HFUN/USDC")?, 10001);
+        assert_eq!(exchange_client.resolve_asset("hfun/usdc")?, 10001);
+        assert_eq!(exchange_client.resolve_asset("Foo/USDC")?, 10002);
+
+        match exchange_client.resolve_asset("foo/usdc") {
+            Err(Error::AmbiguousAsset { candidates, .. }) => {
+                assert_eq!(candidates, vec!["@2".to_string(), "@3".to_string()])
+            }
+            other => panic!("expected an ambiguous asset error, got {other:?}"),
+        }
+        match exchange_client.resolve_asset("PURR/USD") {
+            Err(Error::UnknownAsset { suggestions, .. }) => {
+                assert_eq!(suggestions, vec!["PURR/USDC".to_string()])
+            }
+            other => panic!("expected an unknown asset error, got {other:?}"),
+        }
+        Ok(())
+    }
+
+    fn order_statuses(statuses: serde_json::Value) -> serde_json::Value {
+        json!({
+            "status": "ok",
+            "response": { "type": "order", "data": { "statuses": statuses } }
+        })
+    }
 
-        let address = key
-            .parse::<LocalWallet>()
-            .map_err(|e| Error::PrivateKeyParse(e.to_string()))?
-            .address();
-        let connection_id = keccak(address);
+    async fn replace_order_requests(
+        modify_response: serde_json::Value,
+    ) -> Result<(Result<u64>, Vec<String>)> {
+        let (result, requests) = replace_order_bodies(modify_response, None).await?;
+        let action_types = requests
+            .iter()
+            .filter_map(|body| body["action"]["type"].as_str().map(String::from))
+            .collect();
+        Ok((result, action_types))
+    }
 
-        let (chain, l1_name) = if self.http_client.base_url.eq(MAINNET_API_URL) {
-            (EthChain::Arbitrum, "Arbitrum".to_string())
-        } else {
-            (EthChain::ArbitrumGoerli, "ArbitrumGoerli".to_string())
+    // /exchange request bodies sent by replace_order
+    async fn replace_order_bodies(
+        modify_response: serde_json::Value,
+        cloid: Option<uuid::Uuid>,
+    ) -> Result<(Result<u64>, Vec<serde_json::Value>)> {
+        let transport = Arc::new(
+            eth_transport()
+                .with_response("batchModify", modify_response)
+                .with_response(
+                    "cancel",
+                    json!({
+                        "status": "ok",
+                        "response": { "type": "cancel", "data": { "statuses": ["success"] } }
+                    }),
+                )
+                .with_response(
+                    "order",
+                    order_statuses(json!([{ "resting": { "oid": 9 } }])),
+                ),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let new_order = ClientOrderRequest {
+            cloid,
+            ..eth_bid(1801.0)
         };
+        let result = exchange_client.replace_order(7, new_order, None).await;
+        let requests = transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .map(|(_, body)| body)
+            .collect();
+        Ok((result, requests))
+    }
 
-        let source = "https://hyperliquid.xyz".to_string();
-        let action = serde_json::to_value(Actions::Connect(AgentConnect {
-            chain: l1_name,
-            agent: Agent {
-                source: source.clone(),
-                connection_id,
-            },
-            agent_address: address,
-        }))
-        .map_err(|e| Error::JsonParse(e.to_string()))?;
-        let signature = sign_with_agent(wallet, chain, &source, connection_id)?;
-        let timestamp = next_nonce();
-        Ok((key, self.post(action, signature, timestamp).await?))
+    #[tokio::test]
+    async fn test_replace_order_signs_cloid() -> Result<()> {
+        let cloid = uuid::Uuid::from_u128(0x1234);
+        let (result, requests) = replace_order_bodies(
+            order_statuses(json!([{ "resting": { "oid": 8 } }])),
+            Some(cloid),
+        )
+        .await?;
+        assert_eq!(result?, 8);
+        let modify = &requests[0]["action"]["modifies"][0];
+        assert_eq!(modify["oid"], 7);
+        assert_eq!(modify["order"]["c"], uuid_to_hex_string(cloid));
+
+        // the cloid is part of the signed hash
+        let action: Actions = serde_json::from_value(requests[0]["action"].clone())
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let mut without_cloid = action.clone();
+        if let Actions::BatchModify(bulk_modify) = &mut without_cloid {
+            bulk_modify.modifies[0].order.cloid = None;
+        }
+        assert_ne!(action.hash(1, None)?, without_cloid.hash(1, None)?);
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
+    #[tokio::test]
+    async fn test_replace_order_modifies_in_place() -> Result<()> {
+        let (result, action_types) =
+            replace_order_requests(order_statuses(json!([{ "resting": { "oid": 8 } }]))).await?;
+        assert_eq!(result?, 8);
+        assert_eq!(action_types, vec!["batchModify"]);
+        Ok(())
+    }
 
-    use super::*;
-    use crate::{
-        exchange::order::{Limit, OrderRequest, Trigger},
-        Order,
-    };
+    #[tokio::test]
+    async fn test_replace_order_falls_back_to_cancel_and_order() -> Result<()> {
+        let (result, action_types) = replace_order_requests(order_statuses(
+            json!([{ "error": "Cannot modify this order type" }]),
+        ))
+        .await?;
+        assert_eq!(result?, 9);
+        assert_eq!(action_types, vec!["batchModify", "cancel", "order"]);
 
-    fn get_wallet() -> Result<LocalWallet> {
-        let priv_key = "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e";
-        priv_key
-            .parse::<LocalWallet>()
-            .map_err(|e| Error::Wallet(e.to_string()))
+        // so is a modify of an order that is already gone
+        let (result, action_types) = replace_order_requests(order_statuses(
+            json!([{ "error": "Cannot modify canceled or filled order" }]),
+        ))
+        .await?;
+        assert!(matches!(result, Err(Error::ExchangeRejected(_))));
+        assert_eq!(action_types, vec!["batchModify"]);
+
+        // an invalid new order is reported without touching the resting one
+        let (result, action_types) = replace_order_requests(order_statuses(
+            json!([{ "error": "Price must be divisible by tick size." }]),
+        ))
+        .await?;
+        assert!(matches!(result, Err(Error::ExchangeRejected(_))));
+        assert_eq!(action_types, vec!["batchModify"]);
+        Ok(())
     }
 
     #[test]
-    fn test_limit_order_action_hashing() -> Result<()> {
+    fn test_order_payload_expiry_is_signed() -> Result<()> {
+        let exchange_client = ExchangeClient {
+            transport: Arc::new(MockTransport::new(BaseUrl::Localhost)),
+            wallet: get_wallet()?,
+            meta: serde_json::from_value(eth_meta())
+                .map_err(|e| Error::JsonParse(e.to_string()))?,
+            vault_address: None,
+            coin_to_asset: HashMap::from([("ETH".to_string(), 0)]),
+            asset_to_coin: HashMap::from([(0, "ETH".to_string())]),
+            spot_meta: None,
+            max_slippage_guard: None,
+            max_orders_per_request: DEFAULT_MAX_ORDERS_PER_REQUEST,
+            concurrent_order_chunks: false,
+            debug_rejected_payloads: false,
+        };
+        let order = || eth_bid(1800.0);
+
+        let payload = exchange_client.build_order_payload_with_expiry(
+            vec![order()],
+            Some(1700000000000),
+            None,
+        )?;
+        let body = serde_json::to_value(&payload).map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(body["expiresAfter"], 1700000000000u64);
+
+        let action: Actions = serde_json::from_value(payload.action.clone())
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
         let wallet = get_wallet()?;
-        let action = Actions::Order(BulkOrder {
-            orders: vec![OrderRequest {
-                asset: 1,
-                is_buy: true,
-                limit_px: "2000.0".to_string(),
-                sz: "3.5".to_string(),
-                reduce_only: false,
-                order_type: Order::Limit(Limit {
-                    tif: "Ioc".to_string(),
-                }),
-                cloid: None,
-            }],
-            grouping: "na".to_string(),
+        let signature = sign_action(
+            &action,
+            &wallet,
+            payload.nonce,
+            None,
+            Some(1700000000000),
+            false,
+        )?;
+        assert_eq!(signature, payload.signature);
+        let unexpiring = sign_action(&action, &wallet, payload.nonce, None, None, false)?;
+        assert_ne!(unexpiring, payload.signature);
+
+        let payload = exchange_client.build_order_payload(vec![order()], None)?;
+        let body = serde_json::to_value(&payload).map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert!(body.get("expiresAfter").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tpsl_order_payload_groups_children() -> Result<()> {
+        let exchange_client = ExchangeClient {
+            transport: Arc::new(MockTransport::new(BaseUrl::Localhost)),
+            wallet: get_wallet()?,
+            meta: serde_json::from_value(eth_meta())
+                .map_err(|e| Error::JsonParse(e.to_string()))?,
+            vault_address: None,
+            coin_to_asset: HashMap::from([("ETH".to_string(), 0)]),
+            asset_to_coin: HashMap::from([(0, "ETH".to_string())]),
+            spot_meta: None,
+            max_slippage_guard: None,
+            max_orders_per_request: DEFAULT_MAX_ORDERS_PER_REQUEST,
+            concurrent_order_chunks: false,
+            debug_rejected_payloads: false,
+        };
+        let order = ClientOrderRequest {
+            sz: 0.5,
+            ..eth_bid(1800.0)
+        }
+        .with_take_profit(2000.0)
+        .with_stop_loss(1700.0);
+
+        let payload = exchange_client.build_tpsl_order_payload(order, None)?;
+        assert_eq!(
+            payload.action,
+            json!({
+                "type": "order",
+                "grouping": "normalTpsl",
+                "orders": [
+                    { "a": 0, "b": true, "p": "1800", "s": "0.5", "r": false,
+                      "t": { "limit": { "tif": "Gtc" } } },
+                    { "a": 0, "b": false, "p": "1900", "s": "0.5", "r": true,
+                      "t": { "trigger": { "triggerPx": "2000", "isMarket": true, "tpsl": "tp" } } },
+                    { "a": 0, "b": false, "p": "1615", "s": "0.5", "r": true,
+                      "t": { "trigger": { "triggerPx": "1700", "isMarket": true, "tpsl": "sl" } } }
+                ]
+            })
+        );
+        Ok(())
+    }
+
+    fn margin_mode_transport() -> Arc<MockTransport> {
+        let summary = json!({
+            "accountValue": "1000.0",
+            "totalMarginUsed": "0.0",
+            "totalNtlPos": "0.0",
+            "totalRawUsd": "0.0"
         });
-        let connection_id = action.hash(1583838, None)?;
+        Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "meta",
+                    json!({ "universe": [
+                        { "name": "ETH", "szDecimals": 4, "maxLeverage": 25 },
+                        { "name": "NEW", "szDecimals": 0, "maxLeverage": 3, "onlyIsolated": true }
+                    ] }),
+                )
+                .with_response(
+                    "clearinghouseState",
+                    json!({
+                        "assetPositions": [{
+                            "type": "oneWay",
+                            "position": {
+                                "coin": "ETH", "entryPx": "1800.0",
+                                "leverage": { "type": "isolated", "value": 10, "rawUsd": "-100.0" },
+                                "liquidationPx": null, "marginUsed": "180.0",
+                                "positionValue": "1800.0", "returnOnEquity": "0.0",
+                                "szi": "1.0", "unrealizedPnl": "0.0"
+                            }
+                        }],
+                        "crossMarginSummary": summary,
+                        "marginSummary": summary,
+                        "withdrawable": "800.0"
+                    }),
+                )
+                .with_response(
+                    "updateLeverage",
+                    json!({ "status": "ok", "response": { "type": "default" } }),
+                ),
+        )
+    }
 
-        let signature = sign_l1_action(&wallet, connection_id, true)?;
-        assert_eq!(signature.to_string(), "77957e58e70f43b6b68581f2dc42011fc384538a2e5b7bf42d5b936f19fbb67360721a8598727230f67080efee48c812a6a4442013fd3b0eed509171bef9f23f1c");
+    async fn set_margin_mode_requests(
+        margin: MarginType,
+        leverage: u32,
+    ) -> Result<(Result<Option<ExchangeResponseStatus>>, usize)> {
+        let transport = margin_mode_transport();
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let result = exchange_client
+            .set_margin_mode("ETH", margin, leverage, None)
+            .await;
+        let sent = transport
+            .requests()
+            .iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .count();
+        Ok((result, sent))
+    }
 
-        let signature = sign_l1_action(&wallet, connection_id, false)?;
-        assert_eq!(signature.to_string(), "cd0925372ff1ed499e54883e9a6205ecfadec748f80ec463fe2f84f1209648776377961965cb7b12414186b1ea291e95fd512722427efcbcfb3b0b2bcd4d79d01c");
+    #[tokio::test]
+    async fn test_set_margin_mode_skips_redundant_updates() -> Result<()> {
+        let (result, sent) = set_margin_mode_requests(MarginType::Isolated, 10).await?;
+        assert!(result?.is_none());
+        assert_eq!(sent, 0);
+
+        let (result, sent) = set_margin_mode_requests(MarginType::Cross, 10).await?;
+        assert!(matches!(result?, Some(ExchangeResponseStatus::Ok(_))));
+        assert_eq!(sent, 1);
+
+        let (result, sent) = set_margin_mode_requests(MarginType::Cross, 50).await?;
+        assert!(matches!(result, Err(Error::InvalidOrder(_))));
+        assert_eq!(sent, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_slippage_guard_rejects_far_orders() -> Result<()> {
+        let transport = Arc::new(
+            eth_transport()
+                .with_response("allMids", json!({ "ETH": "2000.0" }))
+                .with_response(
+                    "order",
+                    order_statuses(json!([{ "resting": { "oid": 1 } }])),
+                ),
+        );
+        let mut exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        // off by default
+        exchange_client.order(eth_bid(200.0), None).await?;
+
+        exchange_client.max_slippage_guard = Some(0.05);
+        exchange_client.order(eth_bid(1950.0), None).await?;
+        assert!(matches!(
+            exchange_client.order(eth_bid(200.0), None).await,
+            Err(Error::InvalidOrder(_))
+        ));
+        assert!(matches!(
+            exchange_client
+                .bulk_order(vec![eth_bid(1990.0), eth_bid(2500.0)], None)
+                .await,
+            Err(Error::InvalidOrder(_))
+        ));
+
+        let orders_sent = transport
+            .requests()
+            .iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .count();
+        assert_eq!(orders_sent, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bulk_order_splits_large_batches() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "order",
+            order_statuses(json!([{ "resting": { "oid": 1 } }, { "resting": { "oid": 2 } }])),
+        ));
+        let mut exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        exchange_client.max_orders_per_request = 2;
+        let orders = (0..5).map(|i| eth_bid(1800.0 + i as f64)).collect();
 
+        let response = exchange_client.bulk_order(orders, None).await?;
+        // the mock answers every chunk with two statuses
+        assert_eq!(
+            response.resting_oids(),
+            vec![Some(1), Some(2), Some(1), Some(2), Some(1), Some(2)]
+        );
+
+        let sent: Vec<Vec<String>> = transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .map(|(_, body)| {
+                body["action"]["orders"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|order| order["p"].as_str().unwrap_or_default().to_string())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            sent,
+            vec![vec!["1800", "1801"], vec!["1802", "1803"], vec!["1804"]]
+        );
         Ok(())
     }
 
     #[test]
-    fn test_limit_order_action_hashing_with_cloid() -> Result<()> {
-        let cloid = uuid::Uuid::from_str("1e60610f-0b3d-4205-97c8-8c1fed2ad5ee")
-            .map_err(|_e| uuid::Uuid::new_v4());
-        let wallet = get_wallet()?;
-        let action = Actions::Order(BulkOrder {
-            orders: vec![OrderRequest {
-                asset: 1,
-                is_buy: true,
-                limit_px: "2000.0".to_string(),
-                sz: "3.5".to_string(),
-                reduce_only: false,
-                order_type: Order::Limit(Limit {
-                    tif: "Ioc".to_string(),
-                }),
-                cloid: Some(uuid_to_hex_string(cloid.unwrap())),
-            }],
-            grouping: "na".to_string(),
-        });
-        let connection_id = action.hash(1583838, None)?;
+    fn test_merge_chunk_responses_keeps_failed_chunks_aligned() {
+        let ok = |oid: u64| {
+            serde_json::from_value::<ExchangeResponseStatus>(order_statuses(
+                json!([{ "resting": { "oid": oid } }]),
+            ))
+            .map_err(|e| Error::JsonParse(e.to_string()))
+        };
+        let response = merge_chunk_responses(
+            vec![1, 2, 1],
+            vec![
+                ok(1),
+                Ok(ExchangeResponseStatus::Err("rate limited".to_string())),
+                ok(4),
+            ],
+        );
+        assert_eq!(response.resting_oids(), vec![Some(1), None, None, Some(4)]);
+        let ExchangeResponseStatus::Ok(response) = response else {
+            panic!("merged response should be ok");
+        };
+        assert!(matches!(
+            &response.data.unwrap().statuses[2],
+            ExchangeDataStatus::Error(e) if e == "rate limited"
+        ));
+        assert_eq!(
+            response.raw_data.unwrap()["statuses"][3]["resting"]["oid"],
+            4
+        );
+    }
 
-        let signature = sign_l1_action(&wallet, connection_id, true)?;
-        assert_eq!(signature.to_string(), "d3e894092eb27098077145714630a77bbe3836120ee29df7d935d8510b03a08f456de5ec1be82aa65fc6ecda9ef928b0445e212517a98858cfaa251c4cd7552b1c");
+    #[test]
+    fn test_slippage_price_rounding() {
+        assert_eq!(slippage_price(1800.0, Side::Bid, 0.05, 4), 1890.0);
+        assert_eq!(slippage_price(65432.1, Side::Ask, 0.05, 5), 62160.0);
+        assert_eq!(slippage_price(0.123456, Side::Bid, 0.0, 0), 0.12346);
+        assert_eq!(slippage_price(0.123456, Side::Bid, 0.0, 2), 0.1235);
+    }
 
-        let signature = sign_l1_action(&wallet, connection_id, false)?;
-        assert_eq!(signature.to_string(), "3768349dbb22a7fd770fc9fc50c7b5124a7da342ea579b309f58002ceae49b4357badc7909770919c45d850aabb08474ff2b7b3204ae5b66d9f7375582981f111c");
+    #[tokio::test]
+    async fn test_required_margin_reads_leverage_setting() -> Result<()> {
+        let exchange_client =
+            ExchangeClient::with_transport(leverage_transport(true), get_wallet()?, None, None)
+                .await?;
+        // BTC has no position, its 7x setting is still used
+        for (coin, sz, px, leverage, expected) in [
+            ("ETH", 1.5, 2000.0, 10, 300.0),
+            ("BTC", 0.7, 1000.0, 7, 100.0),
+            ("NEW", -10.0, 3.0, 3, 10.0),
+        ] {
+            let requirement = exchange_client.required_margin(coin, sz, px, None).await?;
+            assert!((requirement.margin - expected).abs() < EPSILON);
+            assert_eq!(requirement.leverage, leverage);
+            assert_eq!(requirement.margin_type, MarginType::Isolated);
+        }
 
+        exchange_client
+            .update_leverage(5, "BTC", true, None)
+            .await?;
+        let requirement = exchange_client
+            .required_margin("BTC", 0.7, 1000.0, None)
+            .await?;
+        assert!((requirement.margin - 140.0).abs() < EPSILON);
+        assert_eq!(requirement.margin_type, MarginType::Cross);
+
+        assert!(matches!(
+            exchange_client
+                .required_margin("DOGE", 1.0, 1.0, None)
+                .await,
+            Err(Error::UnknownAsset { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bulk_order_with_nonce_is_reproducible() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "order",
+            order_statuses(json!([{ "resting": { "oid": 1 } }])),
+        ));
+        let mut exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        exchange_client.max_orders_per_request = 1;
+        let orders = || {
+            (0..2)
+                .map(|i| eth_bid(1800.0 + i as f64))
+                .collect::<Vec<_>>()
+        };
+
+        exchange_client
+            .bulk_order_with_nonce(orders(), 1700000000000, None)
+            .await?;
+        exchange_client
+            .bulk_order_with_nonce(orders(), 1700000000000, None)
+            .await?;
+
+        let sent: Vec<serde_json::Value> = transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .map(|(_, body)| body)
+            .collect();
+        assert_eq!(sent.len(), 4);
+        assert_eq!(sent[0]["nonce"], 1700000000000u64);
+        assert_eq!(sent[1]["nonce"], 1700000000001u64);
+        assert_eq!(sent[0], sent[2]);
+        assert_eq!(sent[1], sent[3]);
+        Ok(())
+    }
+
+    async fn order_via_builder_requests(approved_fee: u64) -> Result<Vec<serde_json::Value>> {
+        let transport = Arc::new(
+            eth_transport()
+                .with_response("maxBuilderFee", json!(approved_fee))
+                .with_response(
+                    "approveBuilderFee",
+                    json!({ "status": "ok", "response": { "type": "default" } }),
+                )
+                .with_response(
+                    "order",
+                    order_statuses(json!([{ "resting": { "oid": 1 } }])),
+                ),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let order = eth_bid(1800.0);
+        let builder = H160::from_low_u64_be(0xb);
+        let (approval, response) = exchange_client
+            .order_via_builder(order, builder, 10, None)
+            .await?;
+        assert_eq!(approval.is_some(), approved_fee < 10);
+        assert_eq!(response.resting_oids(), vec![Some(1)]);
+
+        Ok(transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .map(|(_, body)| body["action"].clone())
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_order_via_builder_approves_missing_fee() -> Result<()> {
+        let actions = order_via_builder_requests(0).await?;
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["type"], "approveBuilderFee");
+        assert_eq!(actions[0]["maxFeeRate"], "0.01%");
+        assert_eq!(
+            actions[1]["builder"],
+            json!({ "b": "0x000000000000000000000000000000000000000b", "f": 10 })
+        );
+
+        let actions = order_via_builder_requests(50).await?;
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0]["type"], "order");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all_for_asset_only_cancels_that_coin() -> Result<()> {
+        let open_order = |coin: &str, oid: u64| {
+            json!({
+                "coin": coin, "limitPx": "100.0", "oid": oid, "side": "B", "sz": "1.0",
+                "timestamp": 1700000000000u64
+            })
+        };
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "meta",
+                    json!({ "universe": [
+                        { "name": "BTC", "szDecimals": 5 },
+                        { "name": "ETH", "szDecimals": 4 }
+                    ] }),
+                )
+                .with_response(
+                    "openOrders",
+                    json!([
+                        open_order("ETH", 1),
+                        open_order("BTC", 2),
+                        open_order("ETH", 3)
+                    ]),
+                )
+                .with_response("cancel", order_statuses(json!(["success", "success"]))),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        assert!(exchange_client
+            .cancel_all_for_asset("ETH", None)
+            .await?
+            .is_some());
+        let (_, body) = transport
+            .requests()
+            .into_iter()
+            .find(|(url_path, _)| url_path == "/exchange")
+            .ok_or(Error::GenericRequest("no cancel sent".to_string()))?;
+        assert_eq!(
+            body["action"]["cancels"],
+            json!([{ "a": 1, "o": 1 }, { "a": 1, "o": 3 }])
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all_concurrent_summarizes_per_coin() -> Result<()> {
+        let open_order = |coin: &str, oid: u64| {
+            json!({
+                "coin": coin, "limitPx": "100.0", "oid": oid, "side": "B", "sz": "1.0",
+                "timestamp": 1700000000000u64
+            })
+        };
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "meta",
+                    json!({ "universe": [
+                        { "name": "BTC", "szDecimals": 5 },
+                        { "name": "ETH", "szDecimals": 4 }
+                    ] }),
+                )
+                .with_response(
+                    "openOrders",
+                    json!([
+                        open_order("ETH", 1),
+                        open_order("BTC", 2),
+                        open_order("ETH", 3),
+                        open_order("DELISTED", 4)
+                    ]),
+                )
+                .with_response("cancel", order_statuses(json!(["success", "success"]))),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        let summaries = exchange_client
+            .cancel_all_concurrent(H160::zero(), 2, None)
+            .await?;
+        let coins: Vec<&str> = summaries.iter().map(|s| s.coin.as_str()).collect();
+        assert_eq!(coins, vec!["BTC", "DELISTED", "ETH"]);
+        assert_eq!(summaries[0].canceled, vec![2]);
+        assert!(summaries[1].canceled.is_empty());
+        assert_eq!(summaries[1].failed.len(), 1);
+        assert_eq!(summaries[2].canceled, vec![1, 3]);
+        assert!(summaries[2].failed.is_empty());
+
+        let cancel_requests = transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .count();
+        assert_eq!(cancel_requests, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_leverage_checks_max_leverage() -> Result<()> {
+        let transport = margin_mode_transport();
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        assert!(matches!(
+            exchange_client.update_leverage(50, "ETH", true, None).await,
+            Err(Error::InvalidOrder(_))
+        ));
+        assert!(matches!(
+            exchange_client.update_leverage(0, "ETH", true, None).await,
+            Err(Error::InvalidOrder(_))
+        ));
+        assert!(transport
+            .requests()
+            .iter()
+            .all(|(url_path, _)| url_path != "/exchange"));
+
+        exchange_client
+            .update_leverage_clamped(50, "ETH", true, None)
+            .await?;
+        let (_, body) = transport
+            .requests()
+            .into_iter()
+            .rfind(|(url_path, _)| url_path == "/exchange")
+            .ok_or(Error::GenericRequest("no action sent".to_string()))?;
+        assert_eq!(body["action"]["leverage"], 25);
         Ok(())
     }
 
     #[test]
-    fn test_tpsl_order_action_hashing() -> Result<()> {
-        for (tpsl, mainnet_signature, testnet_signature) in [
-            (
-                "tp",
-                "e844cafedb695abbc28b3178b136d262327a72bba1012152f3b5b675147e98312d42de83976b05becf768ad882f6f6a1bfa65afadc71f945c2a98473317097ee1b",
-                "f360f6173c1d9a8ff2d8677e1fc4cb787122542985129c42e8bce47c5d58f6910ee42b10fd69af0bff0dd484e2cb8d3fa8fecfec13bde5e31f5d3d47d1e5a73f1b"
-            ),
-            (
-                "sl",
-                "d10f92a81428c0b57fb619f206bca34ad0cb668be8305306804b27491b4f9c257a87dbd87ad5b6e2bce2ae466b004f7572c5080672ed58cdcb3ffaedcd9de9111c",
-                "51b70df3ee8afcdf192390ee79a18b54a8ec92c86653e8ef80b0c90a7cf9850500c6653c4aa2317e7312dfc9b2aeba515d801d7e8af66567539861a6d5eb2d2b1c"
-            )
+    fn test_grouping_encodes_like_its_string() -> Result<()> {
+        for (grouping, name) in [
+            (Grouping::Na, "na"),
+            (Grouping::NormalTpsl, "normalTpsl"),
+            (Grouping::PositionTpsl, "positionTpsl"),
         ] {
-            let wallet = get_wallet()?;
-            let action = Actions::Order(BulkOrder {
-                orders: vec![
-                    OrderRequest {
-                        asset: 1,
-                        is_buy: true,
-                        limit_px: "2000.0".to_string(),
-                        sz: "3.5".to_string(),
-                        reduce_only: false,
-                        order_type: Order::Trigger(Trigger {
-                            trigger_px: "2000.0".to_string(),
-                            is_market: true,
-                            tpsl: tpsl.to_string(),
-                        }),
-                        cloid: None,
-                    }
-                ],
-                grouping: "na".to_string(),
-            });
-            let connection_id = action.hash(1583838, None)?;
+            assert_eq!(
+                serde_json::to_value(grouping).map_err(|e| Error::JsonParse(e.to_string()))?,
+                json!(name)
+            );
+            assert_eq!(
+                rmp_serde::to_vec_named(&grouping).map_err(|e| Error::RmpParse(e.to_string()))?,
+                rmp_serde::to_vec_named(name).map_err(|e| Error::RmpParse(e.to_string()))?
+            );
+        }
+        Ok(())
+    }
 
-            let signature = sign_l1_action(&wallet, connection_id, true)?;
-            assert_eq!(signature.to_string(), mainnet_signature);
+    #[tokio::test]
+    async fn test_approve_agent_with_seeded_key() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "connect",
+            json!({ "status": "ok", "response": { "type": "default" } }),
+        ));
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
 
-            let signature = sign_l1_action(&wallet, connection_id, false)?;
-            assert_eq!(signature.to_string(), testnet_signature);
+        assert_eq!(generate_key_from_seed(7)?, generate_key_from_seed(7)?);
+        assert_ne!(generate_key_from_seed(7)?, generate_key_from_seed(8)?);
+
+        let (key, _) = exchange_client
+            .approve_agent_with_key(generate_key_from_seed(7)?, None)
+            .await?;
+        let (same_key, _) = exchange_client
+            .approve_agent_with_key(generate_key_from_seed(7)?, None)
+            .await?;
+        assert_eq!(key, same_key);
+
+        let bodies: Vec<serde_json::Value> = transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .map(|(_, body)| body)
+            .collect();
+        assert_eq!(bodies[0]["action"], bodies[1]["action"]);
+        assert_eq!(bodies[0]["signature"], bodies[1]["signature"]);
+        assert_eq!(
+            bodies[0]["action"]["agentAddress"],
+            json!(format!("{:?}", agent_address_from_key(&key)?))
+        );
+        Ok(())
+    }
+
+    // Answers activeAssetData from per-asset (coin, is_cross, leverage) settings that
+    // updateLeverage changes when `apply_updates` is set, and everything else from `inner`
+    struct LeverageTransport {
+        inner: MockTransport,
+        settings: std::sync::Mutex<Vec<(&'static str, bool, u32)>>,
+        apply_updates: bool,
+    }
+
+    impl LeverageTransport {
+        // The activeAssetData answer, None for requests `inner` answers
+        fn settings_response(&self, body: &serde_json::Value) -> Result<Option<String>> {
+            let mut settings = self.settings.lock().unwrap_or_else(|e| e.into_inner());
+            if body["type"] == "activeAssetData" {
+                let &(coin, is_cross, leverage) = settings
+                    .iter()
+                    .find(|(coin, _, _)| body["coin"] == *coin)
+                    .ok_or(Error::AssetNotFound)?;
+                let margin = if is_cross { "cross" } else { "isolated" };
+                return Ok(Some(
+                    json!({
+                        "user": body["user"],
+                        "coin": coin,
+                        "leverage": { "type": margin, "value": leverage },
+                    })
+                    .to_string(),
+                ));
+            }
+            let action = &body["action"];
+            if action["type"] == "updateLeverage" && self.apply_updates {
+                let asset = action["asset"].as_u64().ok_or(Error::AssetNotFound)? as usize;
+                settings[asset].1 = action["isCross"] == true;
+                settings[asset].2 = action["leverage"].as_u64().ok_or(Error::AssetNotFound)? as u32;
+            }
+            Ok(None)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for LeverageTransport {
+        fn base_url(&self) -> &str {
+            self.inner.base_url()
+        }
+
+        async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+            let body: serde_json::Value =
+                serde_json::from_str(&data).map_err(|e| Error::JsonParse(e.to_string()))?;
+            match self.settings_response(&body)? {
+                Some(response) => Ok(response),
+                None => self.inner.post(url_path, data).await,
+            }
         }
+    }
+
+    fn leverage_transport(apply_updates: bool) -> Arc<LeverageTransport> {
+        Arc::new(LeverageTransport {
+            inner: MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "meta",
+                    json!({ "universe": [
+                        { "name": "ETH", "szDecimals": 4, "maxLeverage": 25 },
+                        { "name": "BTC", "szDecimals": 5, "maxLeverage": 40 },
+                        { "name": "NEW", "szDecimals": 0, "maxLeverage": 3, "onlyIsolated": true }
+                    ] }),
+                )
+                .with_response(
+                    "updateLeverage",
+                    json!({ "status": "ok", "response": { "type": "default" } }),
+                ),
+            // ETH has a 10x position, BTC has no position but was set to 7x
+            settings: std::sync::Mutex::new(vec![
+                ("ETH", false, 10),
+                ("BTC", false, 7),
+                ("NEW", false, 3),
+            ]),
+            apply_updates,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_set_margin_mode_all() -> Result<()> {
+        let transport = leverage_transport(true);
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let sent = |transport: &LeverageTransport| -> Vec<serde_json::Value> {
+            transport
+                .inner
+                .requests()
+                .into_iter()
+                .filter(|(url_path, _)| url_path == "/exchange")
+                .map(|(_, body)| body["action"].clone())
+                .collect()
+        };
+
+        // NEW only supports isolated margin, BTC keeps its 7x without a position
+        let results = exchange_client
+            .set_margin_mode_all(MarginType::Cross, 1, None)
+            .await?;
+        let coins: Vec<&str> = results.iter().map(|(coin, _)| coin.as_str()).collect();
+        assert_eq!(coins, vec!["BTC", "ETH"]);
+        assert!(results
+            .iter()
+            .all(|(_, result)| matches!(result, Ok(ExchangeResponseStatus::Ok(_)))));
+        let mut actions = sent(&transport);
+        actions.sort_by_key(|action| action["asset"].as_u64());
+        assert_eq!(
+            actions,
+            vec![
+                json!({ "type": "updateLeverage", "asset": 0, "isCross": true, "leverage": 10 }),
+                json!({ "type": "updateLeverage", "asset": 1, "isCross": true, "leverage": 7 }),
+            ]
+        );
+
+        // Everything that can be cross already is
+        assert!(exchange_client
+            .set_margin_mode_all(MarginType::Cross, 4, None)
+            .await?
+            .is_empty());
+        assert_eq!(sent(&transport).len(), 2);
+
+        // An update that doesn't take is reported by the readback
+        let transport = leverage_transport(false);
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let results = exchange_client
+            .set_margin_mode_all(MarginType::Cross, 4, None)
+            .await?;
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|(_, result)| matches!(result, Err(Error::GenericRequest(_)))));
         Ok(())
     }
 
-    #[test]
-    fn test_cancel_action_hashing() -> Result<()> {
+    #[tokio::test]
+    async fn test_ensure_leverage_only_updates_when_different() -> Result<()> {
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "meta",
+                    json!({ "universe": [{ "name": "ETH", "szDecimals": 4, "maxLeverage": 25 }] }),
+                )
+                .with_response(
+                    "activeAssetData",
+                    json!({
+                        "user": "0x0000000000000000000000000000000000000000",
+                        "coin": "ETH",
+                        "leverage": { "type": "cross", "value": 5 },
+                        "maxTradeSzs": ["10.0", "10.0"],
+                        "availableToTrade": ["100.0", "100.0"],
+                        "markPx": "1800.0"
+                    }),
+                )
+                .with_response(
+                    "updateLeverage",
+                    json!({ "status": "ok", "response": { "type": "default" } }),
+                ),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let sent = || {
+            transport
+                .requests()
+                .iter()
+                .filter(|(url_path, _)| url_path == "/exchange")
+                .count()
+        };
+
+        assert!(
+            !exchange_client
+                .ensure_leverage("ETH", 5, MarginType::Cross, None)
+                .await?
+        );
+        assert_eq!(sent(), 0);
+        assert!(
+            exchange_client
+                .ensure_leverage("ETH", 5, MarginType::Isolated, None)
+                .await?
+        );
+        assert!(
+            exchange_client
+                .ensure_leverage("ETH", 10, MarginType::Cross, None)
+                .await?
+        );
+        assert_eq!(sent(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multi_sig_request_format() -> Result<()> {
+        let ok = json!({ "status": "ok", "response": { "type": "default" } });
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "meta",
+                    json!({ "universe": [{ "name": "ETH", "szDecimals": 4, "maxLeverage": 25 }] }),
+                )
+                .with_response("convertToMultiSigUser", ok.clone())
+                .with_response("multiSig", ok),
+        );
         let wallet = get_wallet()?;
-        let action = Actions::Cancel(BulkCancel {
-            cancels: vec![CancelRequest {
-                asset: 1,
-                oid: 82382,
-            }],
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), wallet.clone(), None, None).await?;
+
+        let first = H160::from_str("0x0000000000000000000000000000000000000002")
+            .map_err(|e| Error::GenericParse(e.to_string()))?;
+        let second = H160::from_str("0x0000000000000000000000000000000000000001")
+            .map_err(|e| Error::GenericParse(e.to_string()))?;
+        exchange_client
+            .convert_to_multi_sig_user(vec![first, second], 2, None)
+            .await?;
+
+        let multi_sig_user = H160::from_str("0x0000000000000000000000000000000000000003")
+            .map_err(|e| Error::GenericParse(e.to_string()))?;
+        let action = Actions::UpdateLeverage(UpdateLeverage {
+            asset: 0,
+            is_cross: true,
+            leverage: 5,
         });
-        let connection_id = action.hash(1583838, None)?;
+        let nonce = 1700000000000;
+        let signature = exchange_client.sign_multi_sig_l1_action(
+            &action,
+            multi_sig_user,
+            wallet.address(),
+            nonce,
+            None,
+        )?;
+        exchange_client
+            .multi_sig(multi_sig_user, action, vec![signature], nonce, None)
+            .await?;
 
-        let signature = sign_l1_action(&wallet, connection_id, true)?;
-        assert_eq!(signature.to_string(), "02f76cc5b16e0810152fa0e14e7b219f49c361e3325f771544c6f54e157bf9fa17ed0afc11a98596be85d5cd9f86600aad515337318f7ab346e5ccc1b03425d51b");
+        let requests = transport.requests();
+        let convert = &requests[requests.len() - 2].1["action"];
+        assert_eq!(convert["type"], "convertToMultiSigUser");
+        assert_eq!(
+            convert["signers"],
+            json!({ "authorizedUsers": [second, first], "threshold": 2 }).to_string()
+        );
 
-        let signature = sign_l1_action(&wallet, connection_id, false)?;
-        assert_eq!(signature.to_string(), "6ffebadfd48067663390962539fbde76cfa36f53be65abe2ab72c9db6d0db44457720db9d7c4860f142a484f070c84eb4b9694c3a617c83f0d698a27e55fd5e01c");
+        let (url_path, body) = &requests[requests.len() - 1];
+        assert_eq!(url_path, "/exchange");
+        assert_eq!(body["nonce"], nonce);
+        assert_eq!(body["action"]["type"], "multiSig");
+        assert_eq!(
+            body["action"]["signatures"].as_array().map(Vec::len),
+            Some(1)
+        );
+        assert_eq!(
+            body["action"]["payload"],
+            json!({
+                "multiSigUser": multi_sig_user,
+                "outerSigner": wallet.address(),
+                "action": { "type": "updateLeverage", "asset": 0, "isCross": true, "leverage": 5 }
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_all_meta_rebuilds_every_map() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "spotMeta",
+            json!({
+                "tokens": [
+                    { "name": "USDC", "szDecimals": 8, "weiDecimals": 8, "index": 0,
+                      "tokenId": "0x6d1e7cde53ba9467b783cb7c530ce054", "isCanonical": true },
+                    { "name": "PURR", "szDecimals": 0, "weiDecimals": 5, "index": 1,
+                      "tokenId": "0xc1fb593aeffbeb02f85e0308e9956a90", "isCanonical": true }
+                ],
+                "universe": [
+                    { "name": "PURR/USDC", "tokens": [1, 0], "index": 0, "isCanonical": true }
+                ]
+            }),
+        ));
+        let stale_meta: Meta = serde_json::from_value(json!({
+            "universe": [{ "name": "BTC", "szDecimals": 5 }, { "name": "ETH", "szDecimals": 4 }]
+        }))
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let mut exchange_client =
+            ExchangeClient::with_transport(transport, get_wallet()?, Some(stale_meta), None)
+                .await?;
+        assert_eq!(exchange_client.asset_index("ETH"), Some(1));
+
+        exchange_client.refresh_all_meta().await?;
+        assert_eq!(exchange_client.meta.universe.len(), 1);
+        assert_eq!(exchange_client.asset_index("BTC"), None);
+        assert_eq!(exchange_client.asset_index("ETH"), Some(0));
+        assert_eq!(exchange_client.asset_name(1), None);
+        assert_eq!(exchange_client.asset_index("PURR/USDC"), Some(10000));
+        assert_eq!(exchange_client.asset_name(10000), Some("PURR/USDC"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_order_and_wait() -> Result<()> {
+        let transport = Arc::new(eth_transport().with_response(
+            "order",
+            order_statuses(
+                json!([{ "filled": { "totalSz": "0.01", "avgPx": "1799.5", "oid": 7 } }]),
+            ),
+        ));
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let outcome = exchange_client
+            .order_and_wait(eth_bid(1800.0), Duration::from_secs(5), None)
+            .await?;
+        assert!(matches!(outcome, OrderOutcome::Filled(filled) if filled.oid == 7));
+        // meta and the order, no order status poll
+        assert_eq!(transport.requests().len(), 2);
+
+        let transport = Arc::new(
+            eth_transport()
+                .with_response(
+                    "order",
+                    order_statuses(json!([{ "resting": { "oid": 8 } }])),
+                )
+                .with_response(
+                    "orderStatus",
+                    json!({
+                        "status": "order",
+                        "order": {
+                            "order": {
+                                "coin": "ETH", "side": "B", "limitPx": "1800.0", "sz": "0.0",
+                                "oid": 8, "timestamp": 1700000000000u64, "origSz": "0.01",
+                                "cloid": null
+                            },
+                            "status": "canceled",
+                            "statusTimestamp": 1700000001000u64
+                        }
+                    }),
+                ),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+        let outcome = exchange_client
+            .order_and_wait(eth_bid(1800.0), Duration::from_secs(5), None)
+            .await?;
+        match outcome {
+            OrderOutcome::Terminal(update) => {
+                assert_eq!(update.status, "canceled");
+                assert_eq!(update.order.oid, 8);
+            }
+            outcome => panic!("expected a terminal update, got {outcome:?}"),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_config_skips_matching_coins() -> Result<()> {
+        let ok = json!({ "status": "ok", "response": { "type": "default" } });
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "meta",
+                    json!({ "universe": [
+                        { "name": "ETH", "szDecimals": 4, "maxLeverage": 25 },
+                        { "name": "BTC", "szDecimals": 5, "maxLeverage": 40 }
+                    ] }),
+                )
+                .with_response(
+                    "activeAssetData",
+                    json!({
+                        "user": "0x0000000000000000000000000000000000000000",
+                        "coin": "ETH",
+                        "leverage": { "type": "cross", "value": 5 },
+                        "markPx": "1800.0"
+                    }),
+                )
+                .with_response("updateLeverage", ok.clone())
+                .with_response("scheduleCancel", ok),
+        );
+        let exchange_client =
+            ExchangeClient::with_transport(transport.clone(), get_wallet()?, None, None).await?;
+
+        let report = exchange_client
+            .apply_config(
+                AccountConfig {
+                    coins: vec![
+                        CoinConfig {
+                            coin: "ETH".to_string(),
+                            leverage: 5,
+                            margin: MarginType::Cross,
+                        },
+                        CoinConfig {
+                            coin: "BTC".to_string(),
+                            leverage: 3,
+                            margin: MarginType::Isolated,
+                        },
+                        // above ETH's 25x max
+                        CoinConfig {
+                            coin: "ETH".to_string(),
+                            leverage: 50,
+                            margin: MarginType::Cross,
+                        },
+                    ],
+                    schedule_cancel_after: Some(Duration::from_secs(60)),
+                },
+                None,
+            )
+            .await?;
+        assert_eq!(report.updated, vec!["BTC".to_string()]);
+        assert_eq!(report.unchanged, vec!["ETH".to_string()]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "ETH");
+        assert!(report.schedule_cancel_error.is_none());
 
+        let actions: Vec<serde_json::Value> = transport
+            .requests()
+            .into_iter()
+            .filter(|(url_path, _)| url_path == "/exchange")
+            .map(|(_, body)| body["action"].clone())
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                json!({ "type": "updateLeverage", "asset": 1, "isCross": false, "leverage": 3 }),
+                json!({ "type": "scheduleCancel", "time": report.schedule_cancel_time }),
+            ]
+        );
         Ok(())
     }
 }
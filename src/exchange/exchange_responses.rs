@@ -1,3 +1,4 @@
+use crate::{prelude::*, Error, MarginType, OrderUpdate};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -24,16 +25,49 @@ pub enum ExchangeDataStatus {
     Filled(FilledOrder),
 }
 
+// How an order placed with ExchangeClient::order_and_wait ended
+#[derive(Debug, Clone)]
+pub enum OrderOutcome {
+    // Matched in full as it was placed
+    Filled(FilledOrder),
+    // Rested first, then reached this terminal update: filled, canceled or rejected
+    Terminal(OrderUpdate),
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct ExchangeDataStatuses {
     pub statuses: Vec<ExchangeDataStatus>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
+#[serde(from = "RawExchangeResponse")]
 pub struct ExchangeResponse {
-    #[serde(rename = "type")]
     pub response_type: String,
+    // Statuses of order and cancel style actions, None for actions whose data has another shape
     pub data: Option<ExchangeDataStatuses>,
+    // `response.data` exactly as returned, e.g. the new address for createSubAccount
+    pub raw_data: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct RawExchangeResponse {
+    #[serde(rename = "type")]
+    response_type: String,
+    data: Option<serde_json::Value>,
+}
+
+impl From<RawExchangeResponse> for ExchangeResponse {
+    fn from(raw: RawExchangeResponse) -> Self {
+        let data = raw
+            .data
+            .as_ref()
+            .and_then(|data| ExchangeDataStatuses::deserialize(data).ok());
+        ExchangeResponse {
+            response_type: raw.response_type,
+            data,
+            raw_data: raw.data,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -43,3 +77,344 @@ pub enum ExchangeResponseStatus {
     Ok(ExchangeResponse),
     Err(String),
 }
+
+impl ExchangeResponseStatus {
+    // One entry per submitted order, Some only for orders that are now resting on the book.
+    // Empty when the whole request was rejected.
+    pub fn resting_oids(&self) -> Vec<Option<u64>> {
+        match self {
+            ExchangeResponseStatus::Ok(ExchangeResponse {
+                data: Some(data), ..
+            }) => data
+                .statuses
+                .iter()
+                .map(|status| match status {
+                    ExchangeDataStatus::Resting(resting_order) => Some(resting_order.oid),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Category of the request level error, or else of the first rejected order. None when
+    // nothing was rejected.
+    pub fn error_kind(&self) -> Option<ExchangeErrorKind> {
+        match self {
+            ExchangeResponseStatus::Err(err) => Some(ExchangeErrorKind::classify(err)),
+            ExchangeResponseStatus::Ok(ExchangeResponse {
+                data: Some(data), ..
+            }) => data.statuses.iter().find_map(|status| match status {
+                ExchangeDataStatus::Error(err) => Some(ExchangeErrorKind::classify(err)),
+                _ => None,
+            }),
+            ExchangeResponseStatus::Ok(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExchangeErrorKind {
+    InsufficientMargin,
+    MinNotional,
+    // Too far from the reference price, or a post only order that would have crossed
+    PriceTooAggressive,
+    ReduceOnlyViolation,
+    RateLimited,
+    // The resting order's type can't be modified in place, it has to be canceled and placed
+    // again
+    ModifyUnsupported,
+    Unknown(String),
+}
+
+impl ExchangeErrorKind {
+    // Best effort, by substrings of the messages the exchange currently returns
+    pub fn classify(err: &str) -> ExchangeErrorKind {
+        let lower = err.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|pattern| lower.contains(pattern));
+        if matches(&["insufficient margin"]) {
+            ExchangeErrorKind::InsufficientMargin
+        } else if matches(&["minimum value of"]) {
+            ExchangeErrorKind::MinNotional
+        } else if matches(&[
+            "away from the reference price",
+            "would have immediately matched",
+        ]) {
+            ExchangeErrorKind::PriceTooAggressive
+        } else if matches(&["reduce only order would increase position"]) {
+            ExchangeErrorKind::ReduceOnlyViolation
+        } else if matches(&["too many", "rate limit"]) {
+            ExchangeErrorKind::RateLimited
+        } else if matches(&["cannot modify this order type"]) {
+            ExchangeErrorKind::ModifyUnsupported
+        } else {
+            ExchangeErrorKind::Unknown(err.to_string())
+        }
+    }
+}
+
+// Returned by the IOC order when nothing on the book was within the limit price
+const NO_MATCH_ERROR: &str = "Order could not immediately match against any resting orders";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketOrderFill {
+    pub oid: Option<u64>,
+    pub filled_sz: f64,
+    pub avg_px: f64,
+    pub remaining_sz: f64,
+}
+
+impl MarketOrderFill {
+    // An IOC order that didn't match at all is reported as an empty fill rather than an error
+    pub(crate) fn from_response(response: ExchangeResponseStatus, sz: f64) -> Result<Self> {
+        let data = match response {
+            ExchangeResponseStatus::Ok(ExchangeResponse {
+                data: Some(data), ..
+            }) => data,
+            ExchangeResponseStatus::Ok(_) => {
+                return Err(Error::GenericParse(
+                    "Order response had no statuses".to_string(),
+                ))
+            }
+            ExchangeResponseStatus::Err(err) => return Err(Error::ExchangeRejected(err)),
+        };
+        match data.statuses.into_iter().next() {
+            Some(ExchangeDataStatus::Filled(filled)) => {
+                let filled_sz: f64 = filled
+                    .total_sz
+                    .parse()
+                    .map_err(|_| Error::FloatStringParse)?;
+                Ok(MarketOrderFill {
+                    oid: Some(filled.oid),
+                    filled_sz,
+                    avg_px: filled.avg_px.parse().map_err(|_| Error::FloatStringParse)?,
+                    remaining_sz: (sz - filled_sz).max(0.0),
+                })
+            }
+            Some(ExchangeDataStatus::Error(err)) if err.starts_with(NO_MATCH_ERROR) => {
+                Ok(MarketOrderFill {
+                    oid: None,
+                    filled_sz: 0.0,
+                    avg_px: 0.0,
+                    remaining_sz: sz,
+                })
+            }
+            Some(ExchangeDataStatus::Error(err)) => Err(Error::ExchangeRejected(err)),
+            status => Err(Error::GenericParse(format!(
+                "Unexpected status for market order: {status:?}"
+            ))),
+        }
+    }
+}
+
+// Returned by ExchangeClient::required_margin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginRequirement {
+    pub margin: f64,
+    // The coin's leverage setting, before the tier cap for the order's notional
+    pub leverage: u32,
+    // Cross margin comes out of the shared account balance, isolated margin is moved into
+    // the coin's position
+    pub margin_type: MarginType,
+}
+
+// What ExchangeClient::apply_config had to change
+#[derive(Debug, Default)]
+pub struct AccountConfigReport {
+    // Coins whose leverage or margin mode was updated
+    pub updated: Vec<String>,
+    // Coins that already matched the config
+    pub unchanged: Vec<String>,
+    // Coins left as they were because reading or updating their setting failed
+    pub failed: Vec<(String, Error)>,
+    // When the scheduled cancel fires, in milliseconds. None if none was requested or arming
+    // it failed
+    pub schedule_cancel_time: Option<u64>,
+    pub schedule_cancel_error: Option<Error>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoinCancelSummary {
+    pub coin: String,
+    pub canceled: Vec<u64>,
+    // (oid, reason) for every order that is still open
+    pub failed: Vec<(u64, String)>,
+}
+
+impl CoinCancelSummary {
+    // Statuses come back in the order the cancels were sent, so they are matched to `oids` by
+    // position. A request level failure marks every order of the coin as failed
+    pub(crate) fn from_response(
+        coin: String,
+        oids: Vec<u64>,
+        response: Result<ExchangeResponseStatus>,
+    ) -> Self {
+        let statuses = match response {
+            Ok(ExchangeResponseStatus::Ok(ExchangeResponse {
+                data: Some(data), ..
+            })) => data.statuses,
+            Ok(ExchangeResponseStatus::Ok(_)) => Vec::new(),
+            Ok(ExchangeResponseStatus::Err(err)) => {
+                return CoinCancelSummary::all_failed(coin, oids, err)
+            }
+            Err(err) => return CoinCancelSummary::all_failed(coin, oids, err.to_string()),
+        };
+
+        let mut summary = CoinCancelSummary {
+            coin,
+            canceled: Vec::new(),
+            failed: Vec::new(),
+        };
+        let mut statuses = statuses.into_iter();
+        for oid in oids {
+            match statuses.next() {
+                Some(ExchangeDataStatus::Success) => summary.canceled.push(oid),
+                Some(ExchangeDataStatus::Error(err)) => summary.failed.push((oid, err)),
+                status => summary
+                    .failed
+                    .push((oid, format!("Unexpected status for cancel: {status:?}"))),
+            }
+        }
+        summary
+    }
+
+    fn all_failed(coin: String, oids: Vec<u64>, reason: String) -> Self {
+        CoinCancelSummary {
+            coin,
+            canceled: Vec::new(),
+            failed: oids.into_iter().map(|oid| (oid, reason.clone())).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resting_oids_are_index_aligned() {
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"resting": {"oid": 11}},
+                {"filled": {"totalSz": "0.02", "avgPx": "1891.4", "oid": 12}},
+                {"error": "Order must have minimum value of $10."},
+                {"resting": {"oid": 14}}
+            ]}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response.resting_oids(),
+            vec![Some(11), None, None, Some(14)]
+        );
+
+        let response = ExchangeResponseStatus::Err("User or API Wallet does not exist.".into());
+        assert!(response.resting_oids().is_empty());
+    }
+
+    #[test]
+    fn raw_data_is_kept_for_non_order_actions() {
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "createSubAccount",
+                "data": "0x035605fc2f24d65300227189025e90a0d947f16c"}}"#,
+        )
+        .unwrap();
+        let ExchangeResponseStatus::Ok(response) = response else {
+            panic!("expected an ok response");
+        };
+        assert_eq!(response.response_type, "createSubAccount");
+        assert!(response.data.is_none());
+        assert_eq!(
+            response.raw_data.unwrap(),
+            "0x035605fc2f24d65300227189025e90a0d947f16c"
+        );
+
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "cancel", "data": {"statuses": ["success"]}}}"#,
+        )
+        .unwrap();
+        let ExchangeResponseStatus::Ok(response) = response else {
+            panic!("expected an ok response");
+        };
+        assert_eq!(response.data.unwrap().statuses.len(), 1);
+        assert!(response.raw_data.unwrap()["statuses"].is_array());
+    }
+
+    #[test]
+    fn market_order_fill_reports_partial_fills() {
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"filled": {"totalSz": "0.3", "avgPx": "1891.4", "oid": 12}}
+            ]}}}"#,
+        )
+        .unwrap();
+        let fill = MarketOrderFill::from_response(response, 0.5).unwrap();
+        assert_eq!(fill.oid, Some(12));
+        assert_eq!(fill.filled_sz, 0.3);
+        assert_eq!(fill.avg_px, 1891.4);
+        assert!((fill.remaining_sz - 0.2).abs() < 1e-12);
+
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"error": "Order could not immediately match against any resting orders. asset=4"}
+            ]}}}"#,
+        )
+        .unwrap();
+        let fill = MarketOrderFill::from_response(response, 0.5).unwrap();
+        assert_eq!((fill.filled_sz, fill.remaining_sz), (0.0, 0.5));
+
+        let response = ExchangeResponseStatus::Err("Insufficient margin".to_string());
+        assert!(matches!(
+            MarketOrderFill::from_response(response, 0.5),
+            Err(Error::ExchangeRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_error_kind_classification() {
+        let rejected: ExchangeResponseStatus = serde_json::from_value(serde_json::json!({
+            "status": "ok",
+            "response": { "type": "order", "data": { "statuses": [
+                { "resting": { "oid": 1 } },
+                { "error": "Order must have minimum value of $10." }
+            ] } }
+        }))
+        .unwrap();
+        assert_eq!(rejected.error_kind(), Some(ExchangeErrorKind::MinNotional));
+
+        let kind = |err: &str| ExchangeResponseStatus::Err(err.to_string()).error_kind();
+        assert_eq!(
+            kind("Insufficient margin to place order. asset=4"),
+            Some(ExchangeErrorKind::InsufficientMargin)
+        );
+        assert_eq!(
+            kind("Order price cannot be more than 80% away from the reference price"),
+            Some(ExchangeErrorKind::PriceTooAggressive)
+        );
+        assert_eq!(
+            kind("Post only order would have immediately matched, bbo was 1800@1801"),
+            Some(ExchangeErrorKind::PriceTooAggressive)
+        );
+        assert_eq!(
+            kind("Reduce only order would increase position."),
+            Some(ExchangeErrorKind::ReduceOnlyViolation)
+        );
+        assert_eq!(
+            kind("Too many cumulative requests sent"),
+            Some(ExchangeErrorKind::RateLimited)
+        );
+        assert_eq!(
+            kind("Cannot modify this order type"),
+            Some(ExchangeErrorKind::ModifyUnsupported)
+        );
+        assert!(matches!(
+            kind("Cannot modify canceled or filled order"),
+            Some(ExchangeErrorKind::Unknown(_))
+        ));
+        assert_eq!(
+            kind("Vault not registered"),
+            Some(ExchangeErrorKind::Unknown(
+                "Vault not registered".to_string()
+            ))
+        );
+    }
+}
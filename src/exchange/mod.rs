@@ -5,7 +5,10 @@ mod exchange_responses;
 mod order;
 
 pub use actions::*;
-pub use cancel::{ClientCancelRequestCloid, ClientCancelRequest};
+pub use cancel::{ClientCancelRequest, ClientCancelRequestCloid};
 pub use exchange_client::*;
 pub use exchange_responses::*;
-pub use order::{ClientLimit, ClientOrder, ClientOrderRequest, ClientTrigger, Order};
+pub use order::{
+    ClientLimit, ClientOrder, ClientOrderRequest, ClientTpslOrderRequest, ClientTrigger, Order,
+    OrderType, Side, TimeInForce,
+};
@@ -46,10 +46,102 @@ pub struct OrderRequest {
     pub cloid: Option<String>,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    #[serde(rename = "B")]
+    Bid,
+    #[serde(rename = "A")]
+    Ask,
+}
+
+impl Side {
+    pub fn as_is_buy(&self) -> bool {
+        matches!(self, Side::Bid)
+    }
+}
+
+impl From<bool> for Side {
+    fn from(is_buy: bool) -> Self {
+        if is_buy {
+            Side::Bid
+        } else {
+            Side::Ask
+        }
+    }
+}
+
 pub struct ClientLimit {
     pub tif: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeInForce {
+    // Post only, canceled instead of taking liquidity
+    Alo,
+    Ioc,
+    Gtc,
+    // Only seen on orders read back: market orders placed from the UI and liquidations
+    FrontendMarket,
+    LiquidationMarket,
+}
+
+impl TimeInForce {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeInForce::Alo => "Alo",
+            TimeInForce::Ioc => "Ioc",
+            TimeInForce::Gtc => "Gtc",
+            TimeInForce::FrontendMarket => "FrontendMarket",
+            TimeInForce::LiquidationMarket => "LiquidationMarket",
+        }
+    }
+}
+
+impl From<TimeInForce> for ClientLimit {
+    fn from(tif: TimeInForce) -> Self {
+        ClientLimit {
+            tif: tif.as_str().to_string(),
+        }
+    }
+}
+
+// Order types as the info endpoints report them
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderType {
+    Limit,
+    Market,
+    #[serde(rename = "Stop Market")]
+    StopMarket,
+    #[serde(rename = "Stop Limit")]
+    StopLimit,
+    #[serde(rename = "Take Profit Market")]
+    TakeProfitMarket,
+    #[serde(rename = "Take Profit Limit")]
+    TakeProfitLimit,
+}
+
+impl OrderType {
+    pub fn is_trigger(&self) -> bool {
+        !matches!(self, OrderType::Limit | OrderType::Market)
+    }
+
+    pub fn is_market(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Market | OrderType::StopMarket | OrderType::TakeProfitMarket
+        )
+    }
+
+    // "tp" or "sl" for trigger orders
+    pub fn tpsl(&self) -> Option<&'static str> {
+        match self {
+            OrderType::TakeProfitMarket | OrderType::TakeProfitLimit => Some("tp"),
+            OrderType::StopMarket | OrderType::StopLimit => Some("sl"),
+            OrderType::Limit | OrderType::Market => None,
+        }
+    }
+}
+
 pub struct ClientTrigger {
     pub trigger_px: f64,
     pub is_market: bool,
@@ -62,7 +154,7 @@ pub enum ClientOrder {
 }
 pub struct ClientOrderRequest {
     pub asset: String,
-    pub is_buy: bool,
+    pub side: Side,
     pub reduce_only: bool,
     pub limit_px: f64,
     pub sz: f64,
@@ -70,23 +162,114 @@ pub struct ClientOrderRequest {
     pub order_type: ClientOrder,
 }
 
+// A parent order with take profit and stop loss triggers attached, submitted as one
+// "normalTpsl" group. Built with ClientOrderRequest::with_take_profit / with_stop_loss.
+pub struct ClientTpslOrderRequest {
+    pub parent: ClientOrderRequest,
+    pub take_profit: Option<f64>,
+    pub stop_loss: Option<f64>,
+}
+
+impl ClientTpslOrderRequest {
+    pub fn with_take_profit(mut self, trigger_px: f64) -> Self {
+        self.take_profit = Some(trigger_px);
+        self
+    }
+
+    pub fn with_stop_loss(mut self, trigger_px: f64) -> Self {
+        self.stop_loss = Some(trigger_px);
+        self
+    }
+
+    // Parent first, then the reduce only market triggers closing its full size
+    pub(crate) fn into_orders(
+        self,
+        child_limit_px: impl Fn(f64, Side) -> f64,
+    ) -> Vec<ClientOrderRequest> {
+        let child_side = Side::from(!self.parent.side.as_is_buy());
+        let children = [("tp", self.take_profit), ("sl", self.stop_loss)]
+            .into_iter()
+            .filter_map(|(tpsl, trigger_px)| Some((tpsl, trigger_px?)))
+            .map(|(tpsl, trigger_px)| ClientOrderRequest {
+                asset: self.parent.asset.clone(),
+                side: child_side,
+                reduce_only: true,
+                limit_px: child_limit_px(trigger_px, child_side),
+                sz: self.parent.sz,
+                cloid: None,
+                order_type: ClientOrder::Trigger(ClientTrigger {
+                    trigger_px,
+                    is_market: true,
+                    tpsl: tpsl.to_string(),
+                }),
+            })
+            .collect::<Vec<_>>();
+        std::iter::once(self.parent).chain(children).collect()
+    }
+}
+
+impl From<ClientOrderRequest> for ClientTpslOrderRequest {
+    fn from(parent: ClientOrderRequest) -> Self {
+        ClientTpslOrderRequest {
+            parent,
+            take_profit: None,
+            stop_loss: None,
+        }
+    }
+}
+
+fn validate_positive(field: &str, value: f64) -> Result<()> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(Error::InvalidOrder(format!(
+            "{field} must be a positive number, got {value}"
+        )));
+    }
+    Ok(())
+}
+
 impl ClientOrderRequest {
-    pub(crate) fn convert(self, coin_to_asset: &HashMap<String, u32>) -> Result<OrderRequest> {
-        let order_type = match self.order_type {
-            ClientOrder::Limit(limit) => Order::Limit(Limit { tif: limit.tif }),
+    pub fn with_take_profit(self, trigger_px: f64) -> ClientTpslOrderRequest {
+        ClientTpslOrderRequest::from(self).with_take_profit(trigger_px)
+    }
+
+    pub fn with_stop_loss(self, trigger_px: f64) -> ClientTpslOrderRequest {
+        ClientTpslOrderRequest::from(self).with_stop_loss(trigger_px)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_positive("limit_px", self.limit_px)?;
+        validate_positive("sz", self.sz)?;
+        if let ClientOrder::Trigger(trigger) = &self.order_type {
+            validate_positive("trigger_px", trigger.trigger_px)?;
+        }
+        Ok(())
+    }
+
+    // The order exactly as bulk_order puts it in the action, e.g. for a replay log. `asset` is
+    // looked up in `coin_to_asset` (see ExchangeClient::coin_to_asset)
+    pub fn to_wire(&self, coin_to_asset: &HashMap<String, u32>) -> Result<serde_json::Value> {
+        let asset = *coin_to_asset.get(&self.asset).ok_or(Error::AssetNotFound)?;
+        serde_json::to_value(self.convert(asset)?).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub(crate) fn convert(&self, asset: u32) -> Result<OrderRequest> {
+        self.validate()?;
+        let order_type = match &self.order_type {
+            ClientOrder::Limit(limit) => Order::Limit(Limit {
+                tif: limit.tif.clone(),
+            }),
             ClientOrder::Trigger(trigger) => Order::Trigger(Trigger {
                 trigger_px: float_to_string_for_hashing(trigger.trigger_px),
                 is_market: trigger.is_market,
-                tpsl: trigger.tpsl,
+                tpsl: trigger.tpsl.clone(),
             }),
         };
-        let &asset = coin_to_asset.get(&self.asset).ok_or(Error::AssetNotFound)?;
 
         let cloid = self.cloid.map(uuid_to_hex_string);
 
         Ok(OrderRequest {
             asset,
-            is_buy: self.is_buy,
+            is_buy: self.side.as_is_buy(),
             reduce_only: self.reduce_only,
             limit_px: float_to_string_for_hashing(self.limit_px),
             sz: float_to_string_for_hashing(self.sz),
@@ -95,3 +278,86 @@ impl ClientOrderRequest {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(limit_px: f64, sz: f64) -> ClientOrderRequest {
+        ClientOrderRequest {
+            asset: "ETH".to_string(),
+            side: Side::Bid,
+            reduce_only: false,
+            limit_px,
+            sz,
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Gtc".to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_positive_price_and_size() {
+        assert!(order(1800.0, 0.01).validate().is_ok());
+        assert!(order(f64::MIN_POSITIVE, f64::MIN_POSITIVE)
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_non_positive_price() {
+        for limit_px in [0.0, -0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let err = order(limit_px, 1.0).validate().unwrap_err();
+            assert!(matches!(err, Error::InvalidOrder(msg) if msg.starts_with("limit_px")));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_non_positive_size() {
+        for sz in [0.0, -0.0, -0.01, f64::NAN] {
+            let err = order(1800.0, sz).validate().unwrap_err();
+            assert!(matches!(err, Error::InvalidOrder(msg) if msg.starts_with("sz")));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_non_positive_trigger_price() {
+        let mut order = order(1800.0, 0.01);
+        order.order_type = ClientOrder::Trigger(ClientTrigger {
+            trigger_px: 0.0,
+            is_market: true,
+            tpsl: "sl".to_string(),
+        });
+        let err = order.validate().unwrap_err();
+        assert!(matches!(err, Error::InvalidOrder(msg) if msg.starts_with("trigger_px")));
+    }
+
+    #[test]
+    fn convert_rejects_invalid_order() {
+        assert!(matches!(
+            order(1800.0, 0.0).convert(0),
+            Err(Error::InvalidOrder(_))
+        ));
+    }
+
+    #[test]
+    fn to_wire_matches_the_action_order() -> Result<()> {
+        let coin_to_asset = HashMap::from([("ETH".to_string(), 4)]);
+        assert_eq!(
+            order(1800.0, 0.01).to_wire(&coin_to_asset)?,
+            serde_json::json!({
+                "a": 4, "b": true, "p": "1800", "s": "0.01", "r": false,
+                "t": { "limit": { "tif": "Gtc" } }
+            })
+        );
+
+        let mut unknown = order(1800.0, 0.01);
+        unknown.asset = "DOGE".to_string();
+        assert!(matches!(
+            unknown.to_wire(&coin_to_asset),
+            Err(Error::AssetNotFound)
+        ));
+        Ok(())
+    }
+}
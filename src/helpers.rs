@@ -1,12 +1,21 @@
 use crate::{consts::*, prelude::*, Error};
 use chrono::prelude::Utc;
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::H160,
+};
 use lazy_static::lazy_static;
 use log::info;
-use rand::{thread_rng, Rng};
-use std::sync::atomic::{AtomicU64, Ordering};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
 use uuid::Uuid;
 
-fn now_timestamp_ms() -> u64 {
+pub(crate) fn now_timestamp_ms() -> u64 {
     let now = Utc::now();
     now.timestamp_millis() as u64
 }
@@ -50,10 +59,89 @@ pub(crate) fn uuid_to_hex_string(uuid: Uuid) -> String {
     format!("0x{}", hex_string)
 }
 
+// Numeric type used for amounts parsed out of responses. The `decimal` feature switches it to
+// an exact decimal, the string fields of the response structs always keep the raw value.
+#[cfg(not(feature = "decimal"))]
+pub type Number = f64;
+#[cfg(feature = "decimal")]
+pub type Number = rust_decimal::Decimal;
+
+pub fn parse_number(raw: &str) -> Result<Number> {
+    raw.parse()
+        .map_err(|e| Error::GenericParse(format!("{raw}: {e}")))
+}
+
+// Hyperliquid perps pay funding every hour, so the rates in asset contexts and funding
+// history are hourly. Other venues in predictedFundings report their own interval.
+pub const FUNDING_HOURS_PER_YEAR: u32 = 24 * 365;
+
+// Simple (non compounded) annual rate of an hourly funding rate
+pub fn annualize_funding(rate_per_hour: Number) -> Number {
+    rate_per_hour * Number::from(FUNDING_HOURS_PER_YEAR)
+}
+
+pub fn parse_address(address: &str) -> Result<H160> {
+    H160::from_str(address.trim())
+        .map_err(|e| Error::GenericParse(format!("invalid address {address}: {e}")))
+}
+
+// Address of the agent whose hex private key `approve_agent` returned, with or without 0x
+pub fn agent_address_from_key(key: &str) -> Result<H160> {
+    key.trim()
+        .trim_start_matches("0x")
+        .parse::<LocalWallet>()
+        .map(|wallet| wallet.address())
+        .map_err(|e| Error::PrivateKeyParse(e.to_string()))
+}
+
+// Lets methods take an address as an H160 or as a "0x..." string
+pub trait IntoAddress {
+    fn into_address(self) -> Result<H160>;
+}
+
+impl IntoAddress for H160 {
+    fn into_address(self) -> Result<H160> {
+        Ok(self)
+    }
+}
+
+impl IntoAddress for &H160 {
+    fn into_address(self) -> Result<H160> {
+        Ok(*self)
+    }
+}
+
+impl IntoAddress for &str {
+    fn into_address(self) -> Result<H160> {
+        parse_address(self)
+    }
+}
+
+impl IntoAddress for String {
+    fn into_address(self) -> Result<H160> {
+        parse_address(&self)
+    }
+}
+
+impl IntoAddress for &String {
+    fn into_address(self) -> Result<H160> {
+        parse_address(self)
+    }
+}
+
 pub(crate) fn generate_random_key() -> Result<[u8; 32]> {
+    generate_key_with_rng(&mut thread_rng())
+}
+
+// Reproducible keys for tests, e.g. with ExchangeClient::approve_agent_with_key. Never use
+// one for a real account: anyone who knows the seed knows the key
+pub fn generate_key_from_seed(seed: u64) -> Result<[u8; 32]> {
+    generate_key_with_rng(&mut StdRng::seed_from_u64(seed))
+}
+
+fn generate_key_with_rng(rng: &mut impl Rng) -> Result<[u8; 32]> {
     let mut arr = [0u8; 32];
-    thread_rng()
-        .try_fill(&mut arr[..])
+    rng.try_fill(&mut arr[..])
         .map_err(|e| Error::RandGen(e.to_string()))?;
     Ok(arr)
 }
@@ -67,6 +155,70 @@ pub fn truncate_float(float: f64, decimals: u32, round_up: bool) -> f64 {
     float as f64 / pow10
 }
 
+// How the price and size helpers round to what the exchange accepts. Down and Up round
+// towards and away from zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    #[default]
+    Nearest,
+    Down,
+    Up,
+}
+
+impl RoundingMode {
+    fn round(self, value: f64, decimals: i32) -> f64 {
+        let pow10 = 10f64.powi(decimals);
+        let scaled = value.abs() * pow10;
+        // tolerate float noise so 0.3 / 0.1 doesn't floor to 2
+        let rounded = match self {
+            RoundingMode::Nearest => scaled.round(),
+            RoundingMode::Down => (scaled + EPSILON).floor(),
+            RoundingMode::Up => (scaled - EPSILON).ceil(),
+        };
+        value.signum() * rounded / pow10
+    }
+}
+
+// Sizes have at most sz_decimals decimals
+pub fn round_size(sz: f64, sz_decimals: u32, mode: RoundingMode) -> f64 {
+    mode.round(sz, sz_decimals as i32)
+}
+
+// Perp prices have at most 5 significant figures and 6 - sz_decimals decimals, integer prices
+// are always accepted. The mode applies at whichever of the two limits is tighter, so with
+// Down 1234.56 becomes 1234.5 and with Up 1234.6. Spot prices allow 8 - sz_decimals
+// decimals instead, which this doesn't account for.
+pub fn round_price(px: f64, sz_decimals: u32, mode: RoundingMode) -> f64 {
+    if px == 0.0 {
+        return 0.0;
+    }
+    let sig_fig_decimals = 4 - px.abs().log10().floor() as i32;
+    let decimals = sig_fig_decimals.min(6 - sz_decimals as i32).max(0);
+    mode.round(px, decimals)
+}
+
+// Converts an integer amount in the token's smallest unit (wei) to whole tokens,
+// e.g. ("150000000", 8) -> 1.5. The crate itself doesn't call these two: spot balances come
+// back in whole tokens already. They are for callers handling wei amounts, such as the ones
+// spot_deploy_user_genesis and spot_deploy_genesis take
+pub fn token_amount_to_f64(raw: &str, decimals: u32) -> Result<f64> {
+    let raw: f64 = raw.parse().map_err(|_| Error::FloatStringParse)?;
+    Ok(raw / 10f64.powi(decimals as i32))
+}
+
+// Inverse of token_amount_to_f64, rounding to the nearest unit: (1.5, 8) -> "150000000"
+pub fn f64_to_token_amount(amount: f64, decimals: u32) -> String {
+    let shifted = format!("{:.*}", decimals as usize, amount).replace('.', "");
+    let (sign, digits) = match shifted.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", shifted.as_str()),
+    };
+    match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        digits => format!("{sign}{digits}"),
+    }
+}
+
 pub fn bps_diff(x: f64, y: f64) -> u16 {
     if x.abs() < EPSILON {
         INF_BPS
@@ -99,6 +251,92 @@ impl BaseUrl {
     }
 }
 
+// Candle intervals supported by candleSnapshot and the candle subscription
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Interval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "3m")]
+    ThreeMinutes,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "2h")]
+    TwoHours,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "8h")]
+    EightHours,
+    #[serde(rename = "12h")]
+    TwelveHours,
+    #[serde(rename = "1d")]
+    OneDay,
+    #[serde(rename = "3d")]
+    ThreeDays,
+    #[serde(rename = "1w")]
+    OneWeek,
+    #[serde(rename = "1M")]
+    OneMonth,
+}
+
+impl Interval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interval::OneMinute => "1m",
+            Interval::ThreeMinutes => "3m",
+            Interval::FiveMinutes => "5m",
+            Interval::FifteenMinutes => "15m",
+            Interval::ThirtyMinutes => "30m",
+            Interval::OneHour => "1h",
+            Interval::TwoHours => "2h",
+            Interval::FourHours => "4h",
+            Interval::EightHours => "8h",
+            Interval::TwelveHours => "12h",
+            Interval::OneDay => "1d",
+            Interval::ThreeDays => "3d",
+            Interval::OneWeek => "1w",
+            Interval::OneMonth => "1M",
+        }
+    }
+}
+
+impl FromStr for Interval {
+    type Err = Error;
+
+    fn from_str(interval: &str) -> Result<Self> {
+        match interval {
+            "1m" => Ok(Interval::OneMinute),
+            "3m" => Ok(Interval::ThreeMinutes),
+            "5m" => Ok(Interval::FiveMinutes),
+            "15m" => Ok(Interval::FifteenMinutes),
+            "30m" => Ok(Interval::ThirtyMinutes),
+            "1h" => Ok(Interval::OneHour),
+            "2h" => Ok(Interval::TwoHours),
+            "4h" => Ok(Interval::FourHours),
+            "8h" => Ok(Interval::EightHours),
+            "12h" => Ok(Interval::TwelveHours),
+            "1d" => Ok(Interval::OneDay),
+            "3d" => Ok(Interval::ThreeDays),
+            "1w" => Ok(Interval::OneWeek),
+            "1M" => Ok(Interval::OneMonth),
+            _ => Err(Error::GenericParse(format!(
+                "unknown candle interval {interval}"
+            ))),
+        }
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 lazy_static! {
     static ref CUR_NONCE: AtomicU64 =
         AtomicU64::new(now_timestamp_ms());
@@ -108,6 +346,98 @@ lazy_static! {
 mod tests {
     use super::*;
 
+    #[test]
+    fn rounding_modes() {
+        assert_eq!(round_size(1.23456, 3, RoundingMode::default()), 1.235);
+        assert_eq!(round_size(1.23456, 3, RoundingMode::Down), 1.234);
+        assert_eq!(round_size(1.23412, 3, RoundingMode::Up), 1.235);
+        assert_eq!(round_size(0.3 / 0.1, 0, RoundingMode::Down), 3.0);
+        assert_eq!(round_size(-1.2345, 2, RoundingMode::Down), -1.23);
+
+        assert_eq!(round_price(1234.56, 1, RoundingMode::Nearest), 1234.6);
+        assert_eq!(round_price(1234.56, 1, RoundingMode::Down), 1234.5);
+        assert_eq!(round_price(0.0123456, 2, RoundingMode::Up), 0.0124);
+        assert_eq!(round_price(0.0123456, 0, RoundingMode::Down), 0.012345);
+        assert_eq!(round_price(123456.7, 5, RoundingMode::Up), 123457.0);
+    }
+
+    #[test]
+    fn agent_address_from_key_matches_wallet() -> Result<()> {
+        let expected = parse_address("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266")?;
+        let key = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        assert_eq!(agent_address_from_key(key)?, expected);
+        assert_eq!(agent_address_from_key(&format!("0x{key}"))?, expected);
+        assert!(matches!(
+            agent_address_from_key("not a key"),
+            Err(Error::PrivateKeyParse(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn addresses_parse_from_strings() -> Result<()> {
+        let address = H160::from_low_u64_be(0xabc);
+        assert_eq!(
+            "0x0000000000000000000000000000000000000abc".into_address()?,
+            address
+        );
+        assert_eq!(
+            " 0000000000000000000000000000000000000ABC"
+                .to_string()
+                .into_address()?,
+            address
+        );
+        assert_eq!(address.into_address()?, address);
+        assert!(matches!(
+            parse_address("0x1234"),
+            Err(Error::GenericParse(msg)) if msg.contains("0x1234")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn interval_round_trips_through_strings() -> Result<()> {
+        assert_eq!("15m".parse::<Interval>()?, Interval::FifteenMinutes);
+        assert_eq!(Interval::OneMonth.as_str(), "1M");
+        assert_eq!("1m".parse::<Interval>()?, Interval::OneMinute);
+        assert!("1y".parse::<Interval>().is_err());
+        assert_eq!(
+            serde_json::to_string(&Interval::FourHours)
+                .map_err(|e| Error::JsonParse(e.to_string()))?,
+            r#""4h""#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn token_amounts_round_trip() -> Result<()> {
+        assert_eq!(token_amount_to_f64("150000000", 8)?, 1.5);
+        assert_eq!(token_amount_to_f64("1", 0)?, 1.0);
+        assert!(token_amount_to_f64("1.2.3", 8).is_err());
+        assert_eq!(f64_to_token_amount(1.5, 8), "150000000");
+        assert_eq!(f64_to_token_amount(0.1 + 0.2, 6), "300000");
+        assert_eq!(f64_to_token_amount(0.0000001, 5), "0");
+        assert_eq!(f64_to_token_amount(-2.25, 2), "-225");
+        let raw = f64_to_token_amount(123.456789, 6);
+        assert_eq!(token_amount_to_f64(&raw, 6)?, 123.456789);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_number_keeps_decimal_digits() -> Result<()> {
+        assert_eq!(
+            annualize_funding(parse_number("0.0000125")?),
+            parse_number("0.1095")?
+        );
+        let number = parse_number("0.00076")?;
+        #[cfg(not(feature = "decimal"))]
+        assert_eq!(number, 0.00076);
+        #[cfg(feature = "decimal")]
+        assert_eq!(number.to_string(), "0.00076");
+        assert!(parse_number("abc").is_err());
+        Ok(())
+    }
+
     #[test]
     fn float_to_string_for_hashing_test() {
         assert_eq!(float_to_string_for_hashing(0.), "0".to_string()); 
@@ -127,4 +457,20 @@ mod tests {
         assert_eq!(float_to_string_for_hashing(987654321.0), "987654321".to_string());
         assert_eq!(float_to_string_for_hashing(987654321.), "987654321".to_string());
     }
+
+    #[test]
+    fn next_nonce_is_unique_across_threads() {
+        let handles: Vec<_> = (0..8)
+            .map(|_| std::thread::spawn(|| (0..1000).map(|_| next_nonce()).collect::<Vec<_>>()))
+            .collect();
+
+        let mut nonces: Vec<u64> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        let total = nonces.len();
+        nonces.sort_unstable();
+        nonces.dedup();
+        assert_eq!(nonces.len(), total);
+    }
 }
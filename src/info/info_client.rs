@@ -1,26 +1,52 @@
 use crate::{
+    consts::{NONCE_MAX_AGE_MS, NONCE_MAX_LEAD_MS},
+    helpers::{now_timestamp_ms, parse_number, IntoAddress},
     info::{
-        CandlesSnapshotResponse, FundingHistoryResponse, L2SnapshotResponse, OpenOrdersResponse,
-        RecentTradesResponse, UserFillsResponse, UserStateResponse,
+        AccountReplay, AccountSnapshot, ActiveAssetDataResponse, AssetCtx, CandlesSnapshotResponse,
+        CurrentFunding, DelegationResponse, DelegatorSummaryResponse, FrontendOrder,
+        FundingHistoryResponse, HistoricalOrder, L2SnapshotResponse, LedgerUpdate,
+        OpenOrdersResponse, OrderStatusResponse, PerpDeployAuctionStatus, PredictedFunding,
+        RecentTradesResponse, ReferralResponse, RiskSummary, SpotBalance, SpotUserStateResponse,
+        TwapHistoryResponse, UserFeesResponse, UserFillsResponse, UserFundingResponse,
+        UserStateResponse, VaultDetailsResponse,
     },
-    meta::Meta,
+    meta::{AssetStatus, MarginTable, Meta, SpotMeta},
     prelude::*,
-    req::HttpClient,
+    req::{HttpClient, Transport},
     ws::{Subscription, WsManager},
-    BaseUrl, Error, Message,
+    BaseUrl, Error, Interval, Message, OrderUpdate,
 };
 
 use ethers::types::H160;
+use log::warn;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tokio::sync::mpsc::UnboundedSender;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+    time::{sleep, timeout_at, Instant},
+};
+
+const ORDER_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+// Perp funding is paid on the hour
+const FUNDING_INTERVAL_MS: u64 = 60 * 60 * 1000;
+// Most fills a single userFillsByTime response returns
+const USER_FILLS_PAGE_LIMIT: usize = 2000;
+// Most entries a single userFunding response returns
+const USER_FUNDING_PAGE_LIMIT: usize = 500;
+
+// venues that don't list a coin come back as null
+type RawPredictedFundings = Vec<(String, Vec<(String, Option<PredictedFunding>)>)>;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CandleSnapshotRequest {
     coin: String,
-    interval: String,
+    interval: Interval,
     start_time: u64,
     end_time: u64,
 }
@@ -33,6 +59,10 @@ pub enum InfoRequest {
     UserState {
         user: H160,
     },
+    #[serde(rename = "spotClearinghouseState")]
+    SpotUserState {
+        user: H160,
+    },
     #[serde(rename = "batchClearinghouseStates")]
     UserStates {
         users: Vec<H160>,
@@ -40,12 +70,42 @@ pub enum InfoRequest {
     OpenOrders {
         user: H160,
     },
+    FrontendOpenOrders {
+        user: H160,
+    },
+    HistoricalOrders {
+        user: H160,
+    },
+    ActiveAssetData {
+        user: H160,
+        coin: String,
+    },
     Meta,
+    SpotMeta,
+    MetaAndAssetCtxs,
     AllMids,
     UserFills {
         user: H160,
     },
     #[serde(rename_all = "camelCase")]
+    UserFillsByTime {
+        user: H160,
+        start_time: u64,
+        end_time: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UserNonFundingLedgerUpdates {
+        user: H160,
+        start_time: u64,
+        end_time: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UserFunding {
+        user: H160,
+        start_time: u64,
+        end_time: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
     FundingHistory {
         coin: String,
         start_time: u64,
@@ -61,22 +121,77 @@ pub enum InfoRequest {
     CandleSnapshot {
         req: CandleSnapshotRequest,
     },
+    UserFees {
+        user: H160,
+    },
+    Referral {
+        user: H160,
+    },
+    PredictedFundings,
+    PerpDeployAuctionStatus,
+    MaxBuilderFee {
+        user: H160,
+        builder: H160,
+    },
+    Delegations {
+        user: H160,
+    },
+    OrderStatus {
+        user: H160,
+        oid: u64,
+    },
+    DelegatorSummary {
+        user: H160,
+    },
+    TwapHistory {
+        user: H160,
+    },
+    #[serde(rename_all = "camelCase")]
+    VaultDetails {
+        vault_address: H160,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        user: Option<H160>,
+    },
 }
 
 pub struct InfoClient {
-    pub http_client: HttpClient,
+    pub transport: Arc<dyn Transport>,
     pub(crate) ws_manager: Option<WsManager>,
+    // Resend an http l2Book snapshot to book subscribers whenever the websocket reconnects
+    pub l2_book_resnapshot: bool,
 }
 
 impl InfoClient {
     pub async fn new(client: Option<Client>, base_url: Option<BaseUrl>) -> Result<InfoClient> {
-        let client = client.unwrap_or_default();
+        let client = match client {
+            Some(client) => client,
+            None => HttpClient::build_client(None)?,
+        };
         let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
 
-        Ok(InfoClient {
-            http_client: HttpClient { client, base_url },
+        Ok(InfoClient::with_transport(Arc::new(HttpClient {
+            client,
+            base_url,
+            redact_logs: false,
+            in_flight: HttpClient::default_in_flight(),
+        })))
+    }
+
+    pub fn with_transport(transport: Arc<dyn Transport>) -> InfoClient {
+        InfoClient {
+            transport,
             ws_manager: None,
-        })
+            l2_book_resnapshot: false,
+        }
+    }
+
+    async fn connect_ws_manager(&self) -> Result<WsManager> {
+        let url = format!("ws{}/ws", &self.transport.base_url()[4..]);
+        if self.l2_book_resnapshot {
+            WsManager::with_l2_book_resnapshot(url, Arc::clone(&self.transport)).await
+        } else {
+            WsManager::with_transport(url, Arc::clone(&self.transport)).await
+        }
     }
 
     pub async fn subscribe(
@@ -85,9 +200,7 @@ impl InfoClient {
         sender_channel: UnboundedSender<Message>,
     ) -> Result<u32> {
         if self.ws_manager.is_none() {
-            let ws_manager =
-                WsManager::new(format!("ws{}/ws", &self.http_client.base_url[4..])).await?;
-            self.ws_manager = Some(ws_manager);
+            self.ws_manager = Some(self.connect_ws_manager().await?);
         }
 
         let identifier =
@@ -100,11 +213,26 @@ impl InfoClient {
             .await
     }
 
+    // Unstable, see `WsManager::subscribe_raw`
+    pub async fn subscribe_raw(
+        &mut self,
+        subscription: serde_json::Value,
+        sender_channel: UnboundedSender<serde_json::Value>,
+    ) -> Result<u32> {
+        if self.ws_manager.is_none() {
+            self.ws_manager = Some(self.connect_ws_manager().await?);
+        }
+
+        self.ws_manager
+            .as_mut()
+            .ok_or(Error::WsManagerNotFound)?
+            .subscribe_raw(subscription, sender_channel)
+            .await
+    }
+
     pub async fn unsubscribe(&mut self, subscription_id: u32) -> Result<()> {
         if self.ws_manager.is_none() {
-            let ws_manager =
-                WsManager::new(format!("ws{}/ws", &self.http_client.base_url[4..])).await?;
-            self.ws_manager = Some(ws_manager);
+            self.ws_manager = Some(self.connect_ws_manager().await?);
         }
 
         self.ws_manager
@@ -114,27 +242,127 @@ impl InfoClient {
             .await
     }
 
-    pub async fn open_orders(&self, address: H160) -> Result<Vec<OpenOrdersResponse>> {
+    // Websocket round trip time measured from the manager's periodic pings
+    pub async fn ws_latency(&self) -> Option<Duration> {
+        match &self.ws_manager {
+            Some(ws_manager) => ws_manager.latency().await,
+            None => None,
+        }
+    }
+
+    // openOrders doesn't report order types or tif, use frontend_open_orders for those
+    pub async fn open_orders(&self, address: impl IntoAddress) -> Result<Vec<OpenOrdersResponse>> {
+        let address = address.into_address()?;
         let input = InfoRequest::OpenOrders { user: address };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
-    pub async fn user_state(&self, address: H160) -> Result<UserStateResponse> {
+    // Open orders along with their order type, trigger and tif
+    pub async fn frontend_open_orders(
+        &self,
+        address: impl IntoAddress,
+    ) -> Result<Vec<FrontendOrder>> {
+        let address = address.into_address()?;
+        let input = InfoRequest::FrontendOpenOrders { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // The user's most recent orders with their final status, in the same shape as
+    // frontend_open_orders
+    pub async fn historical_orders(
+        &self,
+        address: impl IntoAddress,
+    ) -> Result<Vec<HistoricalOrder>> {
+        let address = address.into_address()?;
+        let input = InfoRequest::HistoricalOrders { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Unlike user_state this reports the leverage setting even without an open position
+    pub async fn active_asset_data(
+        &self,
+        address: impl IntoAddress,
+        coin: &str,
+    ) -> Result<ActiveAssetDataResponse> {
+        let address = address.into_address()?;
+        let input = InfoRequest::ActiveAssetData {
+            user: address,
+            coin: coin.to_string(),
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn user_state(&self, address: impl IntoAddress) -> Result<UserStateResponse> {
+        let address = address.into_address()?;
         let input = InfoRequest::UserState { user: address };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
+    pub async fn risk_summary(&self, address: impl IntoAddress) -> Result<RiskSummary> {
+        self.user_state(address).await?.risk_summary()
+    }
+
+    // clearinghouseState has no time parameter, so the account at a past `time` is rebuilt
+    // instead: position sizes and realized pnl from fills, plus funding, on top of `start`.
+    // Only what happened after `start.time` is fetched, so an earlier result can be rolled
+    // forward; AccountReplay::default() replays the whole history. Margin and unrealized pnl
+    // need prices and aren't rebuilt. The API only serves the most recent 10000 fills, which
+    // bounds how far back a full replay is accurate
+    pub async fn account_at(
+        &self,
+        address: impl IntoAddress,
+        start: &AccountReplay,
+        time: u64,
+    ) -> Result<AccountReplay> {
+        if time < start.time {
+            return Err(Error::GenericRequest(format!(
+                "Can't replay back from {} to {time}",
+                start.time
+            )));
+        }
+        let address = address.into_address()?;
+        let (fills, funding) = tokio::try_join!(
+            self.all_user_fills(address, start.time + 1),
+            self.all_user_funding(address, start.time + 1, time)
+        )?;
+        start.replay(&fills, &funding, time)
+    }
+
+    // Fetches user_state, open_orders and all_mids concurrently
+    pub async fn account_snapshot(&self, address: impl IntoAddress) -> Result<AccountSnapshot> {
+        let address = address.into_address()?;
+        let (user_state, open_orders, all_mids) = tokio::try_join!(
+            self.user_state(address),
+            self.open_orders(address),
+            self.all_mids()
+        )?;
+        Ok(AccountSnapshot {
+            user_state,
+            open_orders,
+            all_mids,
+        })
+    }
+
     pub async fn user_states(&self, addresses: Vec<H160>) -> Result<Vec<UserStateResponse>> {
         let input = InfoRequest::UserStates { users: addresses };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
@@ -142,23 +370,206 @@ impl InfoClient {
         let input = InfoRequest::Meta;
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
+    // Listed, delisted and isolated only flags of a perp, from a fresh meta query
+    pub async fn asset_status(&self, coin: &str) -> Result<AssetStatus> {
+        Ok(self.meta().await?.asset_status(coin))
+    }
+
+    pub async fn spot_meta(&self) -> Result<SpotMeta> {
+        let input = InfoRequest::SpotMeta;
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    async fn raw_spot_user_state(&self, address: H160) -> Result<SpotUserStateResponse> {
+        let input = InfoRequest::SpotUserState { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Token balances of the spot account, with each token's id looked up in spotMeta
+    pub async fn spot_user_state(&self, address: impl IntoAddress) -> Result<Vec<SpotBalance>> {
+        let address = address.into_address()?;
+        let (spot_user_state, spot_meta) =
+            tokio::try_join!(self.raw_spot_user_state(address), self.spot_meta())?;
+
+        spot_user_state
+            .balances
+            .into_iter()
+            .map(|balance| {
+                let token_id = spot_meta
+                    .tokens
+                    .iter()
+                    .find(|token| token.index == balance.token)
+                    .map(|token| token.token_id.clone());
+                let parse =
+                    |amount: &str| amount.parse::<f64>().map_err(|_| Error::FloatStringParse);
+                Ok(SpotBalance {
+                    total: parse(&balance.total)?,
+                    hold: parse(&balance.hold)?,
+                    coin: balance.coin,
+                    token: balance.token,
+                    token_id,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn margin_tables(&self) -> Result<Vec<(u32, MarginTable)>> {
+        Ok(self.meta().await?.margin_tables)
+    }
+
     pub async fn all_mids(&self) -> Result<HashMap<String, String>> {
         let input = InfoRequest::AllMids;
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
-    pub async fn user_fills(&self, address: H160) -> Result<Vec<UserFillsResponse>> {
+    pub async fn user_fills(&self, address: impl IntoAddress) -> Result<Vec<UserFillsResponse>> {
+        let address = address.into_address()?;
         let input = InfoRequest::UserFills { user: address };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Fills between `start_time` and `end_time` (now if None), in milliseconds
+    pub async fn user_fills_by_time(
+        &self,
+        address: impl IntoAddress,
+        start_time: u64,
+        end_time: Option<u64>,
+    ) -> Result<Vec<UserFillsResponse>> {
+        let address = address.into_address()?;
+        let input = InfoRequest::UserFillsByTime {
+            user: address,
+            start_time,
+            end_time,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Every fill since `since` (milliseconds), oldest first. Pages through user_fills_by_time,
+    // restarting each page at the time of the last fill so none are lost at page boundaries
+    pub async fn all_user_fills(
+        &self,
+        address: impl IntoAddress,
+        since: u64,
+    ) -> Result<Vec<UserFillsResponse>> {
+        self.all_user_fills_paged(address.into_address()?, since, USER_FILLS_PAGE_LIMIT)
+            .await
+    }
+
+    async fn all_user_fills_paged(
+        &self,
+        address: H160,
+        since: u64,
+        page_limit: usize,
+    ) -> Result<Vec<UserFillsResponse>> {
+        let mut fills = Vec::new();
+        let mut seen = HashSet::new();
+        let mut start_time = since;
+        loop {
+            let page = self.user_fills_by_time(address, start_time, None).await?;
+            let page_len = page.len();
+            let mut added = false;
+            for fill in page {
+                start_time = start_time.max(fill.time);
+                if seen.insert((fill.hash.clone(), fill.tid)) {
+                    fills.push(fill);
+                    added = true;
+                }
+            }
+            // A full page of fills sharing one timestamp can't be paged past
+            if page_len < page_limit || !added {
+                break;
+            }
+        }
+        fills.sort_by_key(|fill| fill.time);
+        Ok(fills)
+    }
+
+    // Funding paid or received per coin between `start_time` and `end_time` (now if None)
+    pub async fn user_funding(
+        &self,
+        address: impl IntoAddress,
+        start_time: u64,
+        end_time: Option<u64>,
+    ) -> Result<Vec<UserFundingResponse>> {
+        let address = address.into_address()?;
+        let input = InfoRequest::UserFunding {
+            user: address,
+            start_time,
+            end_time,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Pages through user_funding like all_user_fills does through fills. Funding is paid to
+    // every coin at the same time, so entries are told apart by time and coin
+    async fn all_user_funding(
+        &self,
+        address: H160,
+        start_time: u64,
+        end_time: u64,
+    ) -> Result<Vec<UserFundingResponse>> {
+        let mut funding = Vec::new();
+        let mut seen = HashSet::new();
+        let mut start_time = start_time;
+        loop {
+            let page = self
+                .user_funding(address, start_time, Some(end_time))
+                .await?;
+            let page_len = page.len();
+            let mut added = false;
+            for entry in page {
+                start_time = start_time.max(entry.time);
+                if seen.insert((entry.time, entry.delta.coin.clone())) {
+                    funding.push(entry);
+                    added = true;
+                }
+            }
+            if page_len < USER_FUNDING_PAGE_LIMIT || !added {
+                break;
+            }
+        }
+        funding.sort_by_key(|entry| entry.time);
+        Ok(funding)
+    }
+
+    // Deposits, withdrawals and transfers between `start_time` and `end_time` (now if None)
+    pub async fn user_non_funding_ledger_updates(
+        &self,
+        address: impl IntoAddress,
+        start_time: u64,
+        end_time: Option<u64>,
+    ) -> Result<Vec<LedgerUpdate>> {
+        let address = address.into_address()?;
+        let input = InfoRequest::UserNonFundingLedgerUpdates {
+            user: address,
+            start_time,
+            end_time,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
@@ -175,7 +586,7 @@ impl InfoClient {
         };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
@@ -183,7 +594,7 @@ impl InfoClient {
         let input = InfoRequest::RecentTrades { coin };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
@@ -191,14 +602,55 @@ impl InfoClient {
         let input = InfoRequest::L2Book { coin };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
+    // There is no clock endpoint, the l2Book snapshot is stamped with the server's time.
+    // Returns the server time in ms and the local time halfway through the request.
+    async fn timed_server_time(&self) -> Result<(u64, u64)> {
+        let coin = self
+            .meta()
+            .await?
+            .universe
+            .into_iter()
+            .next()
+            .ok_or(Error::AssetNotFound)?
+            .name;
+        let sent = now_timestamp_ms();
+        let snapshot = self.l2_snapshot(coin).await?;
+        let received = now_timestamp_ms();
+        Ok((snapshot.time, sent + (received - sent) / 2))
+    }
+
+    pub async fn server_time(&self) -> Result<u64> {
+        Ok(self.timed_server_time().await?.0)
+    }
+
+    // Local clock minus server clock in ms, positive when the local clock is ahead
+    pub async fn clock_skew_ms(&self) -> Result<i64> {
+        let (server_time, local_time) = self.timed_server_time().await?;
+        Ok(local_time as i64 - server_time as i64)
+    }
+
+    // Logs a warning when the skew is large enough for timestamp nonces to be rejected
+    pub async fn check_clock_skew(&self) -> Result<i64> {
+        let skew_ms = self.clock_skew_ms().await?;
+        if skew_ms > NONCE_MAX_LEAD_MS as i64 {
+            warn!("local clock is {skew_ms}ms ahead of the server, nonces will be rejected as too new");
+        } else if skew_ms < -(NONCE_MAX_AGE_MS as i64) {
+            warn!(
+                "local clock is {}ms behind the server, nonces will be rejected as too old",
+                -skew_ms
+            );
+        }
+        Ok(skew_ms)
+    }
+
     pub async fn candles_snapshot(
         &self,
         coin: String,
-        interval: String,
+        interval: Interval,
         start_time: u64,
         end_time: u64,
     ) -> Result<Vec<CandlesSnapshotResponse>> {
@@ -212,7 +664,882 @@ impl InfoClient {
         };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        let return_data = self.http_client.post("/info", data).await?;
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn user_fees(&self, address: impl IntoAddress) -> Result<UserFeesResponse> {
+        let address = address.into_address()?;
+        let input = InfoRequest::UserFees { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn referral(&self, address: impl IntoAddress) -> Result<ReferralResponse> {
+        let address = address.into_address()?;
+        let input = InfoRequest::Referral { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
+
+    // asset contexts are in the same order as meta.universe
+    pub async fn meta_and_asset_ctxs(&self) -> Result<(Meta, Vec<AssetCtx>)> {
+        let input = InfoRequest::MetaAndAssetCtxs;
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn current_funding(&self, coin: &str) -> Result<CurrentFunding> {
+        let (meta, asset_ctxs) = self.meta_and_asset_ctxs().await?;
+        let asset_ctx = meta
+            .universe
+            .iter()
+            .position(|asset| asset.name == coin)
+            .and_then(|index| asset_ctxs.get(index))
+            .ok_or(Error::AssetNotFound)?;
+        Ok(CurrentFunding {
+            funding_rate: parse_number(&asset_ctx.funding)?,
+            next_funding_time: (now_timestamp_ms() / FUNDING_INTERVAL_MS + 1) * FUNDING_INTERVAL_MS,
+        })
+    }
+
+    pub async fn predicted_fundings(
+        &self,
+    ) -> Result<Vec<(String, Vec<(String, PredictedFunding)>)>> {
+        let input = InfoRequest::PredictedFundings;
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        let fundings: RawPredictedFundings =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        Ok(fundings
+            .into_iter()
+            .map(|(coin, venues)| {
+                let venues = venues
+                    .into_iter()
+                    .filter_map(|(venue, funding)| Some((venue, funding?)))
+                    .collect();
+                (coin, venues)
+            })
+            .collect())
+    }
+
+    // rate is in tenths of a basis point, 0 if the builder was never approved
+    pub async fn max_builder_fee(
+        &self,
+        address: impl IntoAddress,
+        builder: impl IntoAddress,
+    ) -> Result<u64> {
+        let address = address.into_address()?;
+        let builder = builder.into_address()?;
+        let input = InfoRequest::MaxBuilderFee {
+            user: address,
+            builder,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        let max_fee: Option<u64> =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        Ok(max_fee.unwrap_or_default())
+    }
+
+    pub async fn order_status(
+        &self,
+        address: impl IntoAddress,
+        oid: u64,
+    ) -> Result<OrderStatusResponse> {
+        let address = address.into_address()?;
+        let input = InfoRequest::OrderStatus { user: address, oid };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    async fn terminal_order_status(&self, address: H160, oid: u64) -> Result<Option<OrderUpdate>> {
+        let order = self.order_status(address, oid).await?.order;
+        Ok(order.filter(OrderUpdate::is_terminal))
+    }
+
+    // Waits on the order updates stream when a websocket manager is already running,
+    // otherwise polls order_status
+    pub async fn await_order_terminal(
+        &mut self,
+        address: impl IntoAddress,
+        oid: u64,
+        timeout: Duration,
+    ) -> Result<OrderUpdate> {
+        let address = address.into_address()?;
+        let deadline = Instant::now() + timeout;
+
+        if self.ws_manager.is_some() {
+            let (sender, mut receiver) = unbounded_channel();
+            let subscription_id = self
+                .subscribe(Subscription::OrderUpdates { user: address }, sender)
+                .await?;
+
+            let wait = async {
+                // the order may have finished before the subscription went through
+                if let Some(order) = self.terminal_order_status(address, oid).await? {
+                    return Ok(order);
+                }
+                while let Some(message) = receiver.recv().await {
+                    if let Message::OrderUpdates(order_updates) = message {
+                        if let Some(order) = order_updates
+                            .data
+                            .into_iter()
+                            .find(|update| update.order.oid == oid && update.is_terminal())
+                        {
+                            return Ok(order);
+                        }
+                    }
+                }
+                Err(Error::Websocket("Order updates stream closed".to_string()))
+            };
+            let result = timeout_at(deadline, wait)
+                .await
+                .unwrap_or(Err(Error::OrderTimeout(oid)));
+
+            self.unsubscribe(subscription_id).await?;
+            return result;
+        }
+
+        loop {
+            if let Some(order) = self.terminal_order_status(address, oid).await? {
+                return Ok(order);
+            }
+            if Instant::now() + ORDER_STATUS_POLL_INTERVAL > deadline {
+                return Err(Error::OrderTimeout(oid));
+            }
+            sleep(ORDER_STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    // Every status change of the user's TWAPs, oldest first
+    pub async fn twap_history(
+        &self,
+        address: impl IntoAddress,
+    ) -> Result<Vec<TwapHistoryResponse>> {
+        let address = address.into_address()?;
+        let input = InfoRequest::TwapHistory { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Latest state of each TWAP that is still running
+    pub async fn twap_states(&self, address: impl IntoAddress) -> Result<Vec<TwapHistoryResponse>> {
+        let mut latest: HashMap<u64, TwapHistoryResponse> = HashMap::new();
+        for entry in self.twap_history(address).await? {
+            let Some(twap_id) = entry.twap_id else {
+                continue;
+            };
+            match latest.get(&twap_id) {
+                Some(seen) if seen.time > entry.time => {}
+                _ => {
+                    latest.insert(twap_id, entry);
+                }
+            }
+        }
+
+        let mut running: Vec<TwapHistoryResponse> = latest
+            .into_values()
+            .filter(|entry| entry.status.status == "activated")
+            .collect();
+        running.sort_by_key(|entry| entry.twap_id);
+        Ok(running)
+    }
+
+    pub async fn vault_details(
+        &self,
+        vault: impl IntoAddress,
+        user: Option<H160>,
+    ) -> Result<VaultDetailsResponse> {
+        let input = InfoRequest::VaultDetails {
+            vault_address: vault.into_address()?,
+            user,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Gas price of the current perp dex deploy auction, see ExchangeClient::perp_deploy_register_asset
+    pub async fn perp_deploy_auction_status(&self) -> Result<PerpDeployAuctionStatus> {
+        let input = InfoRequest::PerpDeployAuctionStatus;
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn delegations(&self, address: impl IntoAddress) -> Result<Vec<DelegationResponse>> {
+        let address = address.into_address()?;
+        let input = InfoRequest::Delegations { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn delegator_summary(
+        &self,
+        address: impl IntoAddress,
+    ) -> Result<DelegatorSummaryResponse> {
+        let address = address.into_address()?;
+        let input = InfoRequest::DelegatorSummary { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.transport.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        info::LedgerDelta, parse_number, total_builder_fees, ClientOrder, ClientTrigger,
+        MockTransport, OrderType, Side, TimeInForce, EPSILON,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_predicted_fundings_skips_missing_venues() -> Result<()> {
+        let transport = MockTransport::new(BaseUrl::Localhost).with_response(
+            "predictedFundings",
+            json!([
+                ["BTC", [
+                    ["BinPerp", { "fundingRate": "0.0001", "nextFundingTime": 1733961600000u64 }],
+                    ["BybitPerp", null],
+                    ["HlPerp", {
+                        "fundingRate": "-0.0000125",
+                        "nextFundingTime": 1733958000000u64,
+                        "fundingIntervalHours": 1
+                    }]
+                ]]
+            ]),
+        );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let fundings = info_client.predicted_fundings().await?;
+        assert_eq!(fundings.len(), 1);
+        let (coin, venues) = &fundings[0];
+        assert_eq!(coin, "BTC");
+        assert_eq!(venues.len(), 2);
+        assert_eq!(venues[0].0, "BinPerp");
+        assert_eq!(venues[0].1.funding_rate, parse_number("0.0001")?);
+        assert_eq!(venues[0].1.funding_interval_hours, None);
+        assert_eq!(venues[1].0, "HlPerp");
+        assert_eq!(venues[1].1.funding_rate, parse_number("-0.0000125")?);
+        assert_eq!(venues[1].1.funding_interval_hours, Some(1));
+        assert_eq!(venues[0].1.apr(), None);
+        assert_eq!(venues[1].1.apr(), Some(parse_number("-0.1095")?));
+        Ok(())
+    }
+
+    fn order_status_response(status: &str) -> serde_json::Value {
+        json!({
+            "status": "order",
+            "order": {
+                "order": {
+                    "coin": "ETH",
+                    "side": "B",
+                    "limitPx": "1800.0",
+                    "sz": "0.0",
+                    "oid": 42,
+                    "timestamp": 1700000000000u64,
+                    "origSz": "0.01",
+                    "cloid": null
+                },
+                "status": status,
+                "statusTimestamp": 1700000001000u64
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_await_order_terminal_polls_order_status() -> Result<()> {
+        let user = H160::zero();
+        let transport = MockTransport::new(BaseUrl::Localhost)
+            .with_response("orderStatus", order_status_response("filled"));
+        let mut info_client = InfoClient::with_transport(Arc::new(transport));
+        let order = info_client
+            .await_order_terminal(user, 42, Duration::from_secs(5))
+            .await?;
+        assert_eq!(order.status, "filled");
+
+        let transport = MockTransport::new(BaseUrl::Localhost)
+            .with_response("orderStatus", order_status_response("open"));
+        let mut info_client = InfoClient::with_transport(Arc::new(transport));
+        assert!(matches!(
+            info_client
+                .await_order_terminal(user, 42, Duration::from_millis(10))
+                .await,
+            Err(Error::OrderTimeout(42))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_account_snapshot_combines_queries() -> Result<()> {
+        let summary = json!({
+            "accountValue": "100.0",
+            "totalMarginUsed": "0.0",
+            "totalNtlPos": "0.0",
+            "totalRawUsd": "100.0"
+        });
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "clearinghouseState",
+                    json!({
+                        "assetPositions": [],
+                        "crossMarginSummary": summary,
+                        "marginSummary": summary,
+                        "withdrawable": "100.0"
+                    }),
+                )
+                .with_response("openOrders", json!([]))
+                .with_response("allMids", json!({ "ETH": "1800.5" })),
+        );
+        let info_client = InfoClient::with_transport(transport.clone());
+
+        let snapshot = info_client.account_snapshot(H160::zero()).await?;
+        assert_eq!(snapshot.user_state.withdrawable, "100.0");
+        assert!(snapshot.open_orders.is_empty());
+        assert_eq!(snapshot.all_mids["ETH"], "1800.5");
+        assert_eq!(transport.requests().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spot_user_state_resolves_tokens() -> Result<()> {
+        let transport = MockTransport::new(BaseUrl::Localhost)
+            .with_response(
+                "spotClearinghouseState",
+                json!({ "balances": [
+                    { "coin": "USDC", "token": 0, "hold": "0.0", "total": "14.62548512", "entryNtl": "0.0" },
+                    { "coin": "PURR", "token": 1, "hold": "10.0", "total": "2000.0", "entryNtl": "1234.56" },
+                    { "coin": "NEW", "token": 9, "hold": "0.0", "total": "1.5", "entryNtl": "0.0" }
+                ] }),
+            )
+            .with_response(
+                "spotMeta",
+                json!({
+                    "tokens": [
+                        { "name": "USDC", "szDecimals": 8, "weiDecimals": 8, "index": 0,
+                          "tokenId": "0x6d1e7cde53ba9467b783cb7c530ce054", "isCanonical": true },
+                        { "name": "PURR", "szDecimals": 0, "weiDecimals": 5, "index": 1,
+                          "tokenId": "0xc1fb593aeffbeb02f85e0308e9956a90", "isCanonical": true }
+                    ],
+                    "universe": [
+                        { "name": "PURR/USDC", "tokens": [1, 0], "index": 0, "isCanonical": true }
+                    ]
+                }),
+            );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let balances = info_client.spot_user_state(H160::zero()).await?;
+        assert_eq!(
+            balances,
+            vec![
+                SpotBalance {
+                    coin: "USDC".to_string(),
+                    token: 0,
+                    token_id: Some("0x6d1e7cde53ba9467b783cb7c530ce054".to_string()),
+                    total: 14.62548512,
+                    hold: 0.0,
+                },
+                SpotBalance {
+                    coin: "PURR".to_string(),
+                    token: 1,
+                    token_id: Some("0xc1fb593aeffbeb02f85e0308e9956a90".to_string()),
+                    total: 2000.0,
+                    hold: 10.0,
+                },
+                // not in spotMeta yet
+                SpotBalance {
+                    coin: "NEW".to_string(),
+                    token: 9,
+                    token_id: None,
+                    total: 1.5,
+                    hold: 0.0,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clock_skew_from_book_timestamp() -> Result<()> {
+        let three_days_ago = now_timestamp_ms() - 3 * 24 * 60 * 60 * 1000;
+        let transport = MockTransport::new(BaseUrl::Localhost)
+            .with_response(
+                "meta",
+                json!({ "universe": [{ "name": "BTC", "szDecimals": 5 }] }),
+            )
+            .with_response(
+                "l2Book",
+                json!({ "coin": "BTC", "time": three_days_ago, "levels": [[], []] }),
+            );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        assert_eq!(info_client.server_time().await?, three_days_ago);
+        let skew_ms = info_client.check_clock_skew().await?;
+        assert!(skew_ms > NONCE_MAX_AGE_MS as i64);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_current_funding_picks_coin_ctx() -> Result<()> {
+        let ctx = |funding: &str| {
+            json!({
+                "funding": funding, "openInterest": "100.0", "prevDayPx": "1800.0",
+                "dayNtlVlm": "1000000.0", "premium": null, "oraclePx": "1800.0",
+                "markPx": "1800.5", "midPx": null, "impactPxs": null, "dayBaseVlm": "555.0"
+            })
+        };
+        let transport = MockTransport::new(BaseUrl::Localhost).with_response(
+            "metaAndAssetCtxs",
+            json!([
+                { "universe": [
+                    { "name": "BTC", "szDecimals": 5 },
+                    { "name": "ETH", "szDecimals": 4 }
+                ] },
+                [ctx("0.0000125"), ctx("-0.0001")]
+            ]),
+        );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let funding = info_client.current_funding("ETH").await?;
+        assert_eq!(funding.funding_rate, parse_number("-0.0001")?);
+        assert_eq!(funding.next_funding_time % FUNDING_INTERVAL_MS, 0);
+        assert!(funding.next_funding_time > now_timestamp_ms());
+        assert!(matches!(
+            info_client.current_funding("DOGE").await,
+            Err(Error::AssetNotFound)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_twap_states_keeps_running_twaps() -> Result<()> {
+        let entry = |twap_id: u64, time: u64, status: &str, executed_sz: &str| {
+            json!({
+                "time": time,
+                "state": {
+                    "coin": "ETH", "user": "0x0000000000000000000000000000000000000001",
+                    "side": "B", "sz": "10.0", "executedSz": executed_sz,
+                    "executedNtl": "1800.0", "minutes": 30, "reduceOnly": false,
+                    "randomize": true, "timestamp": 1700000000000u64
+                },
+                "status": { "status": status },
+                "twapId": twap_id
+            })
+        };
+        let transport = MockTransport::new(BaseUrl::Localhost).with_response(
+            "twapHistory",
+            json!([
+                entry(1, 10, "activated", "0.0"),
+                entry(2, 20, "activated", "0.0"),
+                entry(1, 30, "finished", "10.0"),
+                entry(3, 40, "activated", "1.0")
+            ]),
+        );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let running = info_client
+            .twap_states("0x0000000000000000000000000000000000000001")
+            .await?;
+        let ids: Vec<Option<u64>> = running.iter().map(|entry| entry.twap_id).collect();
+        assert_eq!(ids, vec![Some(2), Some(3)]);
+        assert_eq!(running[1].state.executed_sz, "1.0");
+        assert_eq!(running[1].state.side, Side::Bid);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vault_details_with_follower_state() -> Result<()> {
+        let follower = |user: &str, vault_equity: &str| {
+            json!({
+                "user": user, "vaultEquity": vault_equity, "pnl": "12.5",
+                "allTimePnl": "40.0", "daysFollowing": 30,
+                "vaultEntryTime": 1700000000000u64, "lockupUntil": 1700086400000u64
+            })
+        };
+        let transport = Arc::new(MockTransport::new(BaseUrl::Localhost).with_response(
+            "vaultDetails",
+            json!({
+                "name": "Test Vault",
+                "vaultAddress": "0xdfc24b077bc1425ad1dea75bcb6f8158e10df303",
+                "leader": "0x677d831aef5328190852e24f13c46cac05f984e7",
+                "description": "",
+                "portfolio": [],
+                "apr": 0.25,
+                "followerState": follower("0x0000000000000000000000000000000000000001", "100.0"),
+                "leaderFraction": 0.1,
+                "followers": [
+                    follower("Leader", "900.0"),
+                    follower("0x0000000000000000000000000000000000000001", "100.0")
+                ],
+                "isClosed": false,
+                "allowDeposits": true
+            }),
+        ));
+        let info_client = InfoClient::with_transport(transport.clone());
+
+        let user = H160::from_low_u64_be(1);
+        let details = info_client
+            .vault_details("0xdfc24b077bc1425ad1dea75bcb6f8158e10df303", Some(user))
+            .await?;
+        assert_eq!(details.apr, 0.25);
+        assert_eq!(details.total_equity(), parse_number("1000.0")?);
+        assert_eq!(
+            details.follower_state.unwrap().vault_equity,
+            parse_number("100.0")?
+        );
+
+        let (_, body) = transport.requests().remove(0);
+        assert_eq!(
+            body["vaultAddress"],
+            "0xdfc24b077bc1425ad1dea75bcb6f8158e10df303"
+        );
+        assert_eq!(body["user"], "0x0000000000000000000000000000000000000001");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_frontend_open_orders() -> Result<()> {
+        let transport = MockTransport::new(BaseUrl::Localhost).with_response(
+            "frontendOpenOrders",
+            json!([
+                {
+                    "coin": "ETH", "side": "A", "limitPx": "2100.0", "sz": "0.5", "oid": 7,
+                    "timestamp": 1700000000000u64, "triggerCondition": "Price above 2000",
+                    "isTrigger": true, "triggerPx": "2000.0", "children": [],
+                    "isPositionTpsl": false, "reduceOnly": true, "orderType": "Take Profit Limit",
+                    "origSz": "0.5", "tif": null, "cloid": null
+                },
+                {
+                    "coin": "BTC", "side": "B", "limitPx": "60000.0", "sz": "0.01", "oid": 8,
+                    "timestamp": 1700000000001u64, "triggerCondition": "N/A",
+                    "isTrigger": false, "triggerPx": "0.0", "children": [],
+                    "isPositionTpsl": false, "reduceOnly": false, "orderType": "Limit",
+                    "origSz": "0.02", "tif": "Alo", "cloid": "0x00000000000000000000000000000001"
+                }
+            ]),
+        );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let orders = info_client.frontend_open_orders(H160::zero()).await?;
+        assert_eq!(orders.len(), 2);
+        assert!(orders[0].is_trigger && orders[0].reduce_only);
+        assert_eq!(orders[0].trigger_px, "2000.0");
+        assert_eq!(orders[0].tif, None);
+        assert_eq!(orders[0].order_type, OrderType::TakeProfitLimit);
+        assert_eq!(orders[1].order_type, OrderType::Limit);
+        assert_eq!(orders[1].tif, Some(TimeInForce::Alo));
+        assert_eq!(orders[1].orig_sz, "0.02");
+
+        let request = orders[0].to_client_order_request()?;
+        assert!(matches!(
+            request.order_type,
+            ClientOrder::Trigger(ClientTrigger { trigger_px, is_market: false, ref tpsl })
+                if trigger_px == 2000.0 && tpsl == "tp"
+        ));
+        let request = orders[1].to_client_order_request()?;
+        assert!(matches!(request.order_type, ClientOrder::Limit(ref limit) if limit.tif == "Alo"));
+        assert_eq!(request.cloid, Some(uuid::Uuid::from_u128(1)));
+        assert_eq!(request.sz, 0.01);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_user_fills_builder_fees() -> Result<()> {
+        let fill = |builder_fee: Option<&str>| {
+            let mut fill = json!({
+                "closedPnl": "0.0", "coin": "ETH", "crossed": true, "dir": "Open Long",
+                "hash": "0x0", "oid": 1, "px": "1800.0", "side": "B", "startPosition": "0.0",
+                "sz": "0.1", "time": 1700000000000u64, "fee": "0.08"
+            });
+            if let Some(builder_fee) = builder_fee {
+                fill["builderFee"] = json!(builder_fee);
+            }
+            fill
+        };
+        let transport = MockTransport::new(BaseUrl::Localhost).with_response(
+            "userFills",
+            json!([fill(Some("0.018")), fill(None), fill(Some("0.002"))]),
+        );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let fills = info_client.user_fills(H160::zero()).await?;
+        assert_eq!(fills[0].builder_fee, Some(0.018));
+        assert_eq!(fills[1].builder_fee, None);
+        assert!((total_builder_fees(&fills) - 0.02).abs() < 1e-12);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_all_user_fills_pages_and_dedupes() -> Result<()> {
+        let fill = |tid: u64, time: u64| {
+            json!({
+                "closedPnl": "0.0", "coin": "ETH", "crossed": true, "dir": "Open Long",
+                "hash": format!("0x{time}"), "oid": 1, "px": "1800.0", "side": "B",
+                "startPosition": "0.0", "sz": "0.1", "time": time, "tid": tid
+            })
+        };
+        let transport = Arc::new(MockTransport::new(BaseUrl::Localhost).with_response(
+            "userFillsByTime",
+            json!([fill(1, 10), fill(2, 20), fill(3, 20)]),
+        ));
+        let info_client = InfoClient::with_transport(transport.clone());
+
+        let fills = info_client.all_user_fills_paged(H160::zero(), 5, 3).await?;
+        let tids: Vec<u64> = fills.iter().map(|fill| fill.tid).collect();
+        assert_eq!(tids, vec![1, 2, 3]);
+
+        let start_times: Vec<serde_json::Value> = transport
+            .requests()
+            .into_iter()
+            .map(|(_, body)| body["startTime"].clone())
+            .collect();
+        assert_eq!(start_times, vec![json!(5), json!(20)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_account_at_replays_fills_and_funding() -> Result<()> {
+        let fill =
+            |coin: &str, side: &str, start: &str, sz: &str, pnl: &str, time: u64, tid: u64| {
+                json!({
+                    "closedPnl": pnl, "coin": coin, "crossed": true, "dir": "",
+                    "hash": "0x0", "oid": 1, "px": "100.0", "side": side, "startPosition": start,
+                    "sz": sz, "time": time, "tid": tid
+                })
+            };
+        let funding = |coin: &str, usdc: &str, time: u64| {
+            json!({
+                "time": time, "hash": "0x0",
+                "delta": {
+                    "type": "funding", "coin": coin, "usdc": usdc, "szi": "1.0",
+                    "fundingRate": "0.0000125", "nSamples": null
+                }
+            })
+        };
+        let transport = Arc::new(
+            MockTransport::new(BaseUrl::Localhost)
+                .with_response(
+                    "userFillsByTime",
+                    json!([
+                        fill("ETH", "B", "0.0", "1.0", "0.0", 10, 1),
+                        fill("ETH", "B", "1.0", "0.5", "0.0", 20, 2),
+                        fill("ETH", "A", "1.5", "1.5", "15.0", 30, 3),
+                        fill("BTC", "A", "0.0", "0.1", "0.0", 20, 4),
+                        fill("SOL", "B", "0.0", "2.0", "0.0", 20, 5),
+                        fill("SOL", "A", "2.0", "2.0", "4.0", 20, 6)
+                    ]),
+                )
+                .with_response(
+                    "userFunding",
+                    json!([
+                        funding("ETH", "-0.5", 15),
+                        funding("ETH", "-0.25", 25),
+                        funding("BTC", "0.1", 25),
+                        funding("ETH", "-1.0", 35)
+                    ]),
+                ),
+        );
+        let info_client = InfoClient::with_transport(transport.clone());
+
+        let replay = info_client
+            .account_at(H160::zero(), &AccountReplay::default(), 25)
+            .await?;
+        assert_eq!(
+            replay.sizes,
+            HashMap::from([("ETH".to_string(), 1.5), ("BTC".to_string(), -0.1)])
+        );
+        assert!((replay.realized_pnl - 4.0).abs() < EPSILON);
+        assert!((replay.funding + 0.65).abs() < EPSILON);
+
+        // Rolling forward only fetches what came after the start, and coins without a fill
+        // keep their start size
+        let mut start = replay;
+        start.sizes.insert("DOGE".to_string(), 3.0);
+        let replay = info_client.account_at(H160::zero(), &start, 30).await?;
+        assert_eq!(
+            replay,
+            AccountReplay {
+                time: 30,
+                sizes: HashMap::from([("DOGE".to_string(), 3.0), ("BTC".to_string(), -0.1)]),
+                realized_pnl: 19.0,
+                funding: start.funding,
+            }
+        );
+        let requests = transport.requests();
+        for (_, body) in &requests[requests.len() - 2..] {
+            assert_eq!(body["startTime"], json!(26));
+        }
+
+        assert!(info_client
+            .account_at(H160::zero(), &replay, 20)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_non_funding_ledger_updates_are_typed() -> Result<()> {
+        let user = "0x0000000000000000000000000000000000000001";
+        let transport = Arc::new(MockTransport::new(BaseUrl::Localhost).with_response(
+            "userNonFundingLedgerUpdates",
+            json!([
+                { "time": 1, "hash": "0x1", "delta": { "type": "deposit", "usdc": "100.5" } },
+                {
+                    "time": 2, "hash": "0x2",
+                    "delta": { "type": "withdraw", "usdc": "50.0", "nonce": 7, "fee": "1.0" }
+                },
+                {
+                    "time": 3, "hash": "0x3",
+                    "delta": { "type": "accountClassTransfer", "usdc": "10.0", "toPerp": false }
+                },
+                { "time": 4, "hash": "0x4", "delta": { "type": "cStakingTransfer", "token": "HYPE" } }
+            ]),
+        ));
+        let info_client = InfoClient::with_transport(transport.clone());
+
+        let updates = info_client
+            .user_non_funding_ledger_updates(user, 0, Some(10))
+            .await?;
+        assert_eq!(updates[0].delta, LedgerDelta::Deposit { usdc: 100.5 });
+        assert_eq!(
+            updates[1].delta,
+            LedgerDelta::Withdraw {
+                usdc: 50.0,
+                nonce: 7,
+                fee: 1.0
+            }
+        );
+        assert_eq!(
+            updates[2].delta,
+            LedgerDelta::AccountClassTransfer {
+                usdc: 10.0,
+                to_perp: false
+            }
+        );
+        assert_eq!(updates[3].delta, LedgerDelta::Other);
+
+        let (_, body) = &transport.requests()[0];
+        assert_eq!(
+            body,
+            &json!({
+                "type": "userNonFundingLedgerUpdates",
+                "user": user,
+                "startTime": 0,
+                "endTime": 10
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_historical_orders() -> Result<()> {
+        let transport = MockTransport::new(BaseUrl::Localhost).with_response(
+            "historicalOrders",
+            json!([{
+                "order": {
+                    "coin": "ETH", "side": "B", "limitPx": "1800.0", "sz": "0.0", "oid": 9,
+                    "timestamp": 1700000000000u64, "triggerCondition": "N/A",
+                    "isTrigger": false, "triggerPx": "0.0", "children": [],
+                    "isPositionTpsl": false, "reduceOnly": false, "orderType": "Market",
+                    "origSz": "0.1", "tif": "FrontendMarket", "cloid": null
+                },
+                "status": "filled",
+                "statusTimestamp": 1700000000100u64
+            }]),
+        );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let orders = info_client.historical_orders(H160::zero()).await?;
+        assert_eq!(orders[0].status, "filled");
+        assert_eq!(orders[0].order.order_type, OrderType::Market);
+        assert_eq!(orders[0].order.tif, Some(TimeInForce::FrontendMarket));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_perp_deploy_auction_status() -> Result<()> {
+        let transport = Arc::new(MockTransport::new(BaseUrl::Localhost).with_response(
+            "perpDeployAuctionStatus",
+            json!({
+                "startTimeSeconds": 1747656000,
+                "durationSeconds": 111600,
+                "startGas": "500.0",
+                "currentGas": "421.5",
+                "endGas": null
+            }),
+        ));
+        let info_client = InfoClient::with_transport(transport.clone());
+
+        let status = info_client.perp_deploy_auction_status().await?;
+        assert_eq!(status.duration_seconds, 111600);
+        assert_eq!(status.start_gas, 500.0);
+        assert_eq!(status.current_gas, Some(421.5));
+        assert_eq!(status.end_gas, None);
+        assert_eq!(
+            transport.requests()[0].1,
+            json!({ "type": "perpDeployAuctionStatus" })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_risk_summary() -> Result<()> {
+        let position = |coin: &str, szi: &str, position_value: &str| {
+            json!({
+                "type": "oneWay",
+                "position": {
+                    "coin": coin, "szi": szi, "positionValue": position_value,
+                    "entryPx": null, "liquidationPx": null, "marginUsed": "0.0",
+                    "returnOnEquity": "0.0", "unrealizedPnl": "0.0",
+                    "leverage": { "type": "cross", "value": 10 }
+                }
+            })
+        };
+        let summary = json!({
+            "accountValue": "1000.0", "totalMarginUsed": "250.0",
+            "totalNtlPos": "2500.0", "totalRawUsd": "1000.0"
+        });
+        let transport = MockTransport::new(BaseUrl::Localhost).with_response(
+            "clearinghouseState",
+            json!({
+                "assetPositions": [
+                    position("ETH", "1.0", "1800.0"),
+                    position("BTC", "-0.01", "650.0"),
+                    position("SOL", "-1.0", "50.0")
+                ],
+                "crossMarginSummary": summary,
+                "marginSummary": summary,
+                "withdrawable": "750.0"
+            }),
+        );
+        let info_client = InfoClient::with_transport(Arc::new(transport));
+
+        let risk = info_client.risk_summary(H160::zero()).await?;
+        assert_eq!(risk.long_notional, parse_number("1800.0")?);
+        assert_eq!(risk.short_notional, parse_number("700.0")?);
+        assert_eq!(risk.net_notional, parse_number("1100.0")?);
+        assert_eq!(risk.margin_utilization, parse_number("0.25")?);
+        Ok(())
+    }
 }
@@ -1,5 +1,19 @@
-use crate::info::{AssetPosition, Level, MarginSummary};
+use crate::{
+    consts::EPSILON,
+    helpers::{annualize_funding, parse_number, Number},
+    info::{
+        AssetPosition, DailyUserVlm, Level, Leverage, MarginSummary, ReferredBy, ReferrerState,
+        StakingDiscount, TwapState, TwapStatus, VaultFollowerState,
+    },
+    prelude::*,
+    serde_helpers::{number_or_string, number_or_string_f64, option_number_or_string_f64},
+    ClientLimit, ClientOrder, ClientOrderRequest, ClientTrigger, Error, OrderType, OrderUpdate,
+    Side, TimeInForce,
+};
+use ethers::types::H160;
 use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -10,15 +24,126 @@ pub struct UserStateResponse {
     pub withdrawable: String,
 }
 
+// Exposure across all perp positions, see UserStateResponse::risk_summary
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskSummary {
+    pub long_notional: Number,
+    // Positive, like long_notional
+    pub short_notional: Number,
+    // long_notional - short_notional
+    pub net_notional: Number,
+    // Margin in use as a fraction of account value, 0 for an empty account
+    pub margin_utilization: Number,
+}
+
+impl UserStateResponse {
+    // Notionals are position values at the mark price, margin covers cross and isolated
+    // positions alike
+    pub fn risk_summary(&self) -> Result<RiskSummary> {
+        let zero = Number::default();
+        let (mut long_notional, mut short_notional) = (zero, zero);
+        for asset_position in &self.asset_positions {
+            let position = &asset_position.position;
+            let value = parse_number(&position.position_value)?.abs();
+            if parse_number(&position.szi)? < zero {
+                short_notional += value;
+            } else {
+                long_notional += value;
+            }
+        }
+
+        let account_value = parse_number(&self.margin_summary.account_value)?;
+        let margin_used = parse_number(&self.margin_summary.total_margin_used)?;
+        Ok(RiskSummary {
+            long_notional,
+            short_notional,
+            net_notional: long_notional - short_notional,
+            margin_utilization: if account_value > zero {
+                margin_used / account_value
+            } else {
+                zero
+            },
+        })
+    }
+}
+
 #[derive(serde::Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct OpenOrdersResponse {
     pub coin: String,
     pub limit_px: String,
     pub oid: u64,
-    pub side: String,
+    pub side: Side,
+    pub sz: String,
+    pub timestamp: u64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendOrder {
+    pub coin: String,
+    pub limit_px: String,
+    pub oid: u64,
+    pub side: Side,
     pub sz: String,
+    pub orig_sz: String,
     pub timestamp: u64,
+    pub order_type: OrderType,
+    pub is_trigger: bool,
+    pub trigger_px: String,
+    // "N/A" for orders without a trigger
+    pub trigger_condition: String,
+    pub is_position_tpsl: bool,
+    pub reduce_only: bool,
+    // None for trigger orders
+    pub tif: Option<TimeInForce>,
+    pub cloid: Option<String>,
+}
+
+impl FrontendOrder {
+    // The request that would place this order again, for the size still open
+    pub fn to_client_order_request(&self) -> Result<ClientOrderRequest> {
+        let order_type = if self.order_type.is_trigger() {
+            ClientOrder::Trigger(ClientTrigger {
+                trigger_px: self
+                    .trigger_px
+                    .parse()
+                    .map_err(|_| Error::FloatStringParse)?,
+                is_market: self.order_type.is_market(),
+                tpsl: self.order_type.tpsl().unwrap_or_default().to_string(),
+            })
+        } else {
+            ClientOrder::Limit(ClientLimit::from(self.tif.unwrap_or(TimeInForce::Gtc)))
+        };
+        let cloid = self
+            .cloid
+            .as_deref()
+            .map(|cloid| {
+                let bytes = hex::decode(cloid.trim_start_matches("0x"))
+                    .map_err(|e| Error::GenericParse(e.to_string()))?;
+                Uuid::from_slice(&bytes).map_err(|e| Error::GenericParse(e.to_string()))
+            })
+            .transpose()?;
+
+        Ok(ClientOrderRequest {
+            asset: self.coin.clone(),
+            side: self.side,
+            reduce_only: self.reduce_only,
+            limit_px: self.limit_px.parse().map_err(|_| Error::FloatStringParse)?,
+            sz: self.sz.parse().map_err(|_| Error::FloatStringParse)?,
+            cloid,
+            order_type,
+        })
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoricalOrder {
+    pub order: FrontendOrder,
+    // "open", "filled", "canceled", "triggered", "rejected", "marginCanceled", ...
+    pub status: String,
+    pub status_timestamp: u64,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -31,21 +156,251 @@ pub struct UserFillsResponse {
     pub hash: String,
     pub oid: u64,
     pub px: String,
-    pub side: String,
+    pub side: Side,
     pub start_position: String,
     pub sz: String,
     pub time: u64,
+    // Trade id, shared by both sides of a trade
+    #[serde(default)]
+    pub tid: u64,
+    // Charged on top of the exchange fee when the order was routed through a builder
+    #[serde(default, deserialize_with = "option_number_or_string_f64")]
+    pub builder_fee: Option<f64>,
+}
+
+impl UserFillsResponse {
+    // `crossed` marks the taker side of the trade
+    pub fn is_maker(&self) -> bool {
+        !self.crossed
+    }
+
+    // Signed position size before this fill
+    pub fn start_position_number(&self) -> Result<Number> {
+        parse_number(&self.start_position)
+    }
+
+    // Realized pnl of the part of the fill that reduced a position, excluding fees
+    pub fn closed_pnl_number(&self) -> Result<Number> {
+        parse_number(&self.closed_pnl)
+    }
+}
+
+// Builder fees paid over `fills`, which `fee` doesn't include
+pub fn total_builder_fees(fills: &[UserFillsResponse]) -> f64 {
+    fills.iter().filter_map(|fill| fill.builder_fee).sum()
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserFundingResponse {
+    pub time: u64,
+    pub hash: String,
+    pub delta: FundingDelta,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingDelta {
+    pub coin: String,
+    // Negative when the position paid funding
+    #[serde(deserialize_with = "number_or_string_f64")]
+    pub usdc: f64,
+    // Signed position size the funding was charged on
+    #[serde(deserialize_with = "number_or_string_f64")]
+    pub szi: f64,
+    #[serde(deserialize_with = "number_or_string_f64")]
+    pub funding_rate: f64,
+}
+
+// Account state rebuilt from fills and funding, see InfoClient::account_at. The default is
+// the empty account before any fill
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountReplay {
+    // Fills and funding up to and including this time (milliseconds) are counted
+    pub time: u64,
+    // Signed size per coin, coins that are flat are left out
+    pub sizes: HashMap<String, f64>,
+    // Sum of the fills' closed pnl, before fees
+    pub realized_pnl: f64,
+    // Net funding, negative when paid
+    pub funding: f64,
+}
+
+impl AccountReplay {
+    // Rolls the account forward to `time` with the fills and funding after `self.time`.
+    // Entries outside that window are ignored, so overlapping pages don't count twice. Every
+    // fill records the position it started from, so only the latest fill per coin sets its
+    // size and coins without one keep theirs
+    pub fn replay(
+        &self,
+        fills: &[UserFillsResponse],
+        funding: &[UserFundingResponse],
+        time: u64,
+    ) -> Result<AccountReplay> {
+        let in_window = |entry_time: u64| self.time < entry_time && entry_time <= time;
+        let mut replay = self.clone();
+        replay.time = time;
+
+        let mut last_fills: HashMap<&str, &UserFillsResponse> = HashMap::new();
+        for fill in fills.iter().filter(|fill| in_window(fill.time)) {
+            replay.realized_pnl += fill
+                .closed_pnl
+                .parse::<f64>()
+                .map_err(|_| Error::FloatStringParse)?;
+            let last = last_fills.entry(&fill.coin).or_insert(fill);
+            if (fill.time, fill.tid) > (last.time, last.tid) {
+                *last = fill;
+            }
+        }
+        for (coin, fill) in last_fills {
+            let start: f64 = fill
+                .start_position
+                .parse()
+                .map_err(|_| Error::FloatStringParse)?;
+            let sz: f64 = fill.sz.parse().map_err(|_| Error::FloatStringParse)?;
+            let position = if fill.side.as_is_buy() {
+                start + sz
+            } else {
+                start - sz
+            };
+            replay.sizes.insert(coin.to_string(), position);
+        }
+        replay.sizes.retain(|_, position| position.abs() > EPSILON);
+
+        replay.funding += funding
+            .iter()
+            .filter(|funding| in_window(funding.time))
+            .map(|funding| funding.delta.usdc)
+            .sum::<f64>();
+        Ok(replay)
+    }
 }
 
 #[derive(serde::Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct FundingHistoryResponse {
     pub coin: String,
+    // Hourly
     pub funding_rate: String,
     pub premium: String,
     pub time: u64,
 }
 
+impl FundingHistoryResponse {
+    pub fn apr(&self) -> Result<Number> {
+        Ok(annualize_funding(parse_number(&self.funding_rate)?))
+    }
+}
+
+// The Dutch auction pricing the next perp dex deployment: gas falls from `start_gas` towards
+// `end_gas` over `duration_seconds`
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpDeployAuctionStatus {
+    pub start_time_seconds: u64,
+    pub duration_seconds: u64,
+    #[serde(deserialize_with = "number_or_string_f64")]
+    pub start_gas: f64,
+    #[serde(default, deserialize_with = "option_number_or_string_f64")]
+    pub current_gas: Option<f64>,
+    // Only set once the auction has ended
+    #[serde(default, deserialize_with = "option_number_or_string_f64")]
+    pub end_gas: Option<f64>,
+}
+
+// The user's current setting and trading limits for one coin
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveAssetDataResponse {
+    pub user: H160,
+    pub coin: String,
+    pub leverage: Leverage,
+    // [buy, sell]
+    #[serde(default)]
+    pub max_trade_szs: Vec<String>,
+    #[serde(default)]
+    pub available_to_trade: Vec<String>,
+    #[serde(default)]
+    pub mark_px: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct LedgerUpdate {
+    pub time: u64,
+    pub hash: String,
+    pub delta: LedgerDelta,
+}
+
+// Balance changes other than fills and funding. Amounts are in USDC unless noted, and
+// updates of types not modeled here come back as Other
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LedgerDelta {
+    Deposit {
+        #[serde(deserialize_with = "number_or_string_f64")]
+        usdc: f64,
+    },
+    Withdraw {
+        #[serde(deserialize_with = "number_or_string_f64")]
+        usdc: f64,
+        nonce: u64,
+        #[serde(default, deserialize_with = "number_or_string_f64")]
+        fee: f64,
+    },
+    InternalTransfer {
+        #[serde(deserialize_with = "number_or_string_f64")]
+        usdc: f64,
+        user: H160,
+        destination: H160,
+        #[serde(default, deserialize_with = "number_or_string_f64")]
+        fee: f64,
+    },
+    SubAccountTransfer {
+        #[serde(deserialize_with = "number_or_string_f64")]
+        usdc: f64,
+        user: H160,
+        destination: H160,
+    },
+    #[serde(rename_all = "camelCase")]
+    AccountClassTransfer {
+        #[serde(deserialize_with = "number_or_string_f64")]
+        usdc: f64,
+        to_perp: bool,
+    },
+    // `amount` is in `token`, `usdc_value` its value at the time of the transfer
+    #[serde(rename_all = "camelCase")]
+    SpotTransfer {
+        token: String,
+        #[serde(deserialize_with = "number_or_string_f64")]
+        amount: f64,
+        #[serde(deserialize_with = "number_or_string_f64")]
+        usdc_value: f64,
+        user: H160,
+        destination: H160,
+        #[serde(default, deserialize_with = "number_or_string_f64")]
+        fee: f64,
+    },
+    VaultDeposit {
+        vault: H160,
+        #[serde(deserialize_with = "number_or_string_f64")]
+        usdc: f64,
+    },
+    #[serde(rename_all = "camelCase")]
+    VaultWithdraw {
+        vault: H160,
+        user: H160,
+        #[serde(deserialize_with = "number_or_string_f64")]
+        requested_usd: f64,
+        #[serde(deserialize_with = "number_or_string_f64")]
+        net_withdrawn_usd: f64,
+    },
+    RewardsClaim {
+        #[serde(deserialize_with = "number_or_string_f64")]
+        amount: f64,
+    },
+    #[serde(other)]
+    Other,
+}
+
 #[derive(serde::Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct L2SnapshotResponse {
@@ -58,7 +413,7 @@ pub struct L2SnapshotResponse {
 #[serde(rename_all = "camelCase")]
 pub struct RecentTradesResponse {
     pub coin: String,
-    pub side: String,
+    pub side: Side,
     pub px: String,
     pub sz: String,
     pub time: u64,
@@ -88,3 +443,120 @@ pub struct CandlesSnapshotResponse {
     #[serde(rename = "n")]
     pub num_trades: u64,
 }
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFeesResponse {
+    #[serde(deserialize_with = "number_or_string")]
+    pub user_add_rate: Number,
+    #[serde(deserialize_with = "number_or_string")]
+    pub user_cross_rate: Number,
+    #[serde(deserialize_with = "number_or_string")]
+    pub active_referral_discount: Number,
+    pub active_staking_discount: Option<StakingDiscount>,
+    pub daily_user_vlm: Vec<DailyUserVlm>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralResponse {
+    pub referred_by: Option<ReferredBy>,
+    pub cum_vlm: String,
+    pub unclaimed_rewards: String,
+    pub claimed_rewards: String,
+    pub referrer_state: ReferrerState,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DelegationResponse {
+    pub validator: H160,
+    #[serde(deserialize_with = "number_or_string")]
+    pub amount: Number,
+    pub locked_until_timestamp: u64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DelegatorSummaryResponse {
+    #[serde(deserialize_with = "number_or_string")]
+    pub delegated: Number,
+    #[serde(deserialize_with = "number_or_string")]
+    pub undelegated: Number,
+    #[serde(deserialize_with = "number_or_string")]
+    pub total_pending_withdrawal: Number,
+    pub n_pending_withdrawals: u64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct OrderStatusResponse {
+    // "order", or "unknownOid" when there is no such order for the user
+    pub status: String,
+    pub order: Option<OrderUpdate>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct SpotUserStateResponse {
+    pub balances: Vec<SpotBalanceResponse>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotBalanceResponse {
+    pub coin: String,
+    pub token: u32,
+    pub hold: String,
+    pub total: String,
+    pub entry_ntl: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotBalance {
+    pub coin: String,
+    pub token: u32,
+    // None when spotMeta doesn't list the token
+    pub token_id: Option<String>,
+    pub total: f64,
+    // Part of total locked in open orders
+    pub hold: f64,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapHistoryResponse {
+    pub time: u64,
+    pub state: TwapState,
+    pub status: TwapStatus,
+    pub twap_id: Option<u64>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultDetailsResponse {
+    pub name: String,
+    pub vault_address: H160,
+    pub leader: H160,
+    pub description: String,
+    pub apr: f64,
+    // Position of the user passed to vault_details, None if they aren't a follower
+    pub follower_state: Option<VaultFollowerState>,
+    pub followers: Vec<VaultFollowerState>,
+    pub is_closed: bool,
+    pub allow_deposits: bool,
+}
+
+impl VaultDetailsResponse {
+    pub fn total_equity(&self) -> Number {
+        self.followers
+            .iter()
+            .map(|follower| follower.vault_equity)
+            .sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct AccountSnapshot {
+    pub user_state: UserStateResponse,
+    pub open_orders: Vec<OpenOrdersResponse>,
+    pub all_mids: HashMap<String, String>,
+}
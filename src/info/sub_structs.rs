@@ -1,3 +1,9 @@
+use crate::{
+    helpers::{annualize_funding, Number},
+    serde_helpers::number_or_string,
+    Side,
+};
+use ethers::types::H160;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -46,3 +52,140 @@ pub struct Level {
     pub px: String,
     pub sz: String,
 }
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyUserVlm {
+    pub date: String,
+    pub user_cross: String,
+    pub user_add: String,
+    pub exchange: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StakingDiscount {
+    #[serde(deserialize_with = "number_or_string")]
+    pub bps_of_max_supply: Number,
+    #[serde(deserialize_with = "number_or_string")]
+    pub discount: Number,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReferredBy {
+    pub referrer: H160,
+    pub code: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralState {
+    pub cum_vlm: String,
+    pub cum_rewarded_fees_since_referred: String,
+    pub cum_fees_rewarded_to_referrer: String,
+    pub time_joined: u64,
+    pub user: H160,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferrerData {
+    pub code: Option<String>,
+    #[serde(default)]
+    pub referral_states: Vec<ReferralState>,
+    pub required: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReferrerState {
+    pub stage: String,
+    pub data: Option<ReferrerData>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetCtx {
+    pub funding: String,
+    pub open_interest: String,
+    pub prev_day_px: String,
+    pub day_ntl_vlm: String,
+    pub premium: Option<String>,
+    pub oracle_px: String,
+    pub mark_px: String,
+    pub mid_px: Option<String>,
+    pub impact_pxs: Option<Vec<String>>,
+    pub day_base_vlm: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CurrentFunding {
+    // Hourly rate, as a fraction of position notional
+    pub funding_rate: Number,
+    pub next_funding_time: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictedFunding {
+    #[serde(deserialize_with = "number_or_string")]
+    pub funding_rate: Number,
+    pub next_funding_time: u64,
+    pub funding_interval_hours: Option<u64>,
+}
+
+impl CurrentFunding {
+    pub fn apr(&self) -> Number {
+        annualize_funding(self.funding_rate)
+    }
+}
+
+impl PredictedFunding {
+    // `funding_rate` is paid once per `funding_interval_hours`. None when the venue didn't
+    // report its interval
+    pub fn apr(&self) -> Option<Number> {
+        let hours = u32::try_from(self.funding_interval_hours?).ok()?;
+        if hours == 0 {
+            return None;
+        }
+        Some(annualize_funding(self.funding_rate / Number::from(hours)))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapState {
+    pub coin: String,
+    pub user: H160,
+    pub side: Side,
+    // Total size of the TWAP
+    pub sz: String,
+    pub executed_sz: String,
+    pub executed_ntl: String,
+    pub minutes: u64,
+    pub reduce_only: bool,
+    pub randomize: bool,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TwapStatus {
+    // "activated", "finished", "terminated" or "error"
+    pub status: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultFollowerState {
+    // Follower address, or "Leader" for the leader's own stake in the followers list
+    pub user: String,
+    #[serde(deserialize_with = "number_or_string")]
+    pub vault_equity: Number,
+    #[serde(deserialize_with = "number_or_string")]
+    pub pnl: Number,
+    #[serde(deserialize_with = "number_or_string")]
+    pub all_time_pnl: Number,
+    pub days_following: u64,
+    pub vault_entry_time: u64,
+    pub lockup_until: u64,
+}
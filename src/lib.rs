@@ -9,13 +9,27 @@ mod meta;
 mod prelude;
 mod proxy_digest;
 mod req;
+mod serde_helpers;
 mod signature;
 mod ws;
-pub use consts::{EPSILON, LOCAL_API_URL, MAINNET_API_URL, TESTNET_API_URL};
+pub use consts::{
+    EPSILON, LOCAL_API_URL, MAINNET_API_URL, NONCE_MAX_AGE_MS, NONCE_MAX_LEAD_MS, TESTNET_API_URL,
+};
 pub use errors::Error;
 pub use exchange::*;
-pub use helpers::{bps_diff, truncate_float, BaseUrl};
+pub use helpers::{
+    agent_address_from_key, annualize_funding, bps_diff, f64_to_token_amount,
+    generate_key_from_seed, parse_address, parse_number, round_price, round_size,
+    token_amount_to_f64, truncate_float, BaseUrl, Interval, IntoAddress, Number, RoundingMode,
+    FUNDING_HOURS_PER_YEAR,
+};
 pub use info::{info_client::*, *};
 pub use market_maker::{MarketMaker, MarketMakerInput, MarketMakerRestingOrder};
-pub use meta::{AssetMeta, Meta};
+pub use meta::{
+    liquidation_price, AssetMeta, AssetStatus, MarginTable, MarginTier, Meta, SpotAssetMeta,
+    SpotMeta, TokenInfo,
+};
+pub use req::{
+    HttpClient, MockTransport, Transport, DEFAULT_MAX_IN_FLIGHT_REQUESTS, DEFAULT_USER_AGENT,
+};
 pub use ws::*;
@@ -9,7 +9,7 @@ use tokio::sync::mpsc::unbounded_channel;
 use crate::{
     bps_diff, truncate_float, BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder,
     ClientOrderRequest, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, InfoClient,
-    Message, Subscription, EPSILON,
+    Message, Side, Subscription, EPSILON,
 };
 #[derive(Debug)]
 pub struct MarketMakerRestingOrder {
@@ -126,7 +126,7 @@ impl MarketMaker {
                     for fill in fills {
                         let amount: f64 = fill.sz.parse().unwrap();
                         // Update our resting positions whenever we see a fill
-                        if fill.side.eq("B") {
+                        if fill.side == Side::Bid {
                             self.cur_position += amount;
                             self.lower_resting.position -= amount;
                             info!("Fill: bought {amount} {}", self.asset.clone());
@@ -180,19 +180,13 @@ impl MarketMaker {
         false
     }
 
-    async fn place_order(
-        &self,
-        asset: String,
-        amount: f64,
-        price: f64,
-        is_buy: bool,
-    ) -> (f64, u64) {
+    async fn place_order(&self, asset: String, amount: f64, price: f64, side: Side) -> (f64, u64) {
         let order = self
             .exchange_client
             .order(
                 ClientOrderRequest {
                     asset,
-                    is_buy,
+                    side,
                     reduce_only: false,
                     limit_px: price,
                     sz: amount,
@@ -296,7 +290,12 @@ impl MarketMaker {
         // Consider putting a new order up
         if lower_order_amount > EPSILON && lower_change {
             let (amount_resting, oid) = self
-                .place_order(self.asset.clone(), lower_order_amount, lower_price, true)
+                .place_order(
+                    self.asset.clone(),
+                    lower_order_amount,
+                    lower_price,
+                    Side::Bid,
+                )
                 .await;
 
             self.lower_resting.oid = oid;
@@ -313,7 +312,12 @@ impl MarketMaker {
 
         if upper_order_amount > EPSILON && upper_change {
             let (amount_resting, oid) = self
-                .place_order(self.asset.clone(), upper_order_amount, upper_price, false)
+                .place_order(
+                    self.asset.clone(),
+                    upper_order_amount,
+                    upper_price,
+                    Side::Ask,
+                )
                 .await;
             self.upper_resting.oid = oid;
             self.upper_resting.position = amount_resting;
@@ -1,8 +1,93 @@
+use crate::{serde_helpers::number_or_string_f64, MarginType};
 use serde::Deserialize;
+use std::collections::HashMap;
+
+// Spot assets are addressed as 10000 + their index in the spot universe
+pub(crate) const SPOT_ASSET_OFFSET: u32 = 10000;
+
+// Tables with an id below this aren't listed in meta, they have a single tier whose max
+// leverage is the id itself
+const DEFAULT_MARGIN_TABLE_IDS: u32 = 50;
 
 #[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Meta {
     pub universe: Vec<AssetMeta>,
+    #[serde(default)]
+    pub margin_tables: Vec<(u32, MarginTable)>,
+    // Fields the server added after this crate was released
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginTable {
+    pub description: String,
+    pub margin_tiers: Vec<MarginTier>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginTier {
+    // Position notional in USD from which this tier applies
+    #[serde(deserialize_with = "number_or_string_f64")]
+    pub lower_bound: f64,
+    pub max_leverage: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpotMeta {
+    pub universe: Vec<SpotAssetMeta>,
+    pub tokens: Vec<TokenInfo>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotAssetMeta {
+    // "PURR/USDC" for canonical pairs, "@{index}" for the rest
+    pub name: String,
+    // [base, quote] token indices
+    pub tokens: [u32; 2],
+    pub index: u32,
+    pub is_canonical: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub name: String,
+    pub sz_decimals: u32,
+    pub wei_decimals: u32,
+    pub index: u32,
+    pub token_id: String,
+    pub is_canonical: bool,
+}
+
+impl SpotMeta {
+    // Human readable "BASE/QUOTE" name of a spot pair
+    pub fn pair_name(&self, spot_asset: &SpotAssetMeta) -> Option<String> {
+        let token_name = |index: u32| {
+            self.tokens
+                .iter()
+                .find(|token| token.index == index)
+                .map(|token| token.name.as_str())
+        };
+        Some(format!(
+            "{}/{}",
+            token_name(spot_asset.tokens[0])?,
+            token_name(spot_asset.tokens[1])?
+        ))
+    }
+}
+
+impl MarginTable {
+    pub fn max_leverage_at(&self, notional: f64) -> Option<u32> {
+        self.margin_tiers
+            .iter()
+            .rfind(|tier| tier.lower_bound <= notional)
+            .map(|tier| tier.max_leverage)
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -10,4 +95,263 @@ pub struct Meta {
 pub struct AssetMeta {
     pub name: String,
     pub sz_decimals: u32,
+    #[serde(default)]
+    pub max_leverage: u32,
+    #[serde(default)]
+    pub only_isolated: bool,
+    #[serde(default)]
+    pub margin_table_id: Option<u32>,
+    #[serde(default)]
+    pub is_delisted: bool,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetStatus {
+    // Whether the coin is in the perp universe at all
+    pub listed: bool,
+    pub delisted: bool,
+    // Cross margin orders are rejected for these
+    pub only_isolated: bool,
+}
+
+impl AssetStatus {
+    pub fn is_tradable(&self) -> bool {
+        self.listed && !self.delisted
+    }
+}
+
+impl Meta {
+    pub fn asset_status(&self, coin: &str) -> AssetStatus {
+        match self.universe.iter().find(|asset| asset.name == coin) {
+            Some(asset) => AssetStatus {
+                listed: true,
+                delisted: asset.is_delisted,
+                only_isolated: asset.only_isolated,
+            },
+            None => AssetStatus {
+                listed: false,
+                delisted: false,
+                only_isolated: false,
+            },
+        }
+    }
+
+    // Maintenance margin is half of the initial margin at max leverage
+    pub fn maintenance_margin(&self, coin: &str) -> Option<f64> {
+        let asset = self.universe.iter().find(|asset| asset.name == coin)?;
+        if asset.max_leverage == 0 {
+            return None;
+        }
+        Some(1.0 / (2.0 * asset.max_leverage as f64))
+    }
+
+    pub fn margin_table(&self, margin_table_id: u32) -> Option<&MarginTable> {
+        self.margin_tables
+            .iter()
+            .find(|(id, _)| *id == margin_table_id)
+            .map(|(_, table)| table)
+    }
+
+    // Max leverage allowed for a position of `notional` USD, which drops as size grows
+    // for coins with tiered margin tables
+    pub fn max_leverage_at(&self, coin: &str, notional: f64) -> Option<u32> {
+        let asset = self.universe.iter().find(|asset| asset.name == coin)?;
+        let max_leverage = match asset.margin_table_id {
+            Some(id) => match self.margin_table(id) {
+                Some(table) => table.max_leverage_at(notional)?,
+                None if id < DEFAULT_MARGIN_TABLE_IDS => id,
+                None => return None,
+            },
+            None => asset.max_leverage,
+        };
+        (max_leverage > 0).then_some(max_leverage)
+    }
+
+    // Margin locked by opening `sz` at `px` with the account's `leverage` setting, which the
+    // exchange caps at the tier max for the order's notional
+    pub fn initial_margin(&self, coin: &str, sz: f64, px: f64, leverage: u32) -> Option<f64> {
+        let notional = sz.abs() * px;
+        let leverage = leverage.min(self.max_leverage_at(coin, notional)?);
+        if leverage == 0 {
+            return None;
+        }
+        Some(notional / leverage as f64)
+    }
+}
+
+/// Price at which the position's equity falls to its maintenance margin, following
+/// <https://hyperliquid.gitbook.io/hyperliquid-docs/trading/liquidations>:
+///
+/// `liq_px = entry_px - side * margin_available / |szi| / (1 - mmf * side)`
+///
+/// where `side` is 1 for longs and -1 for shorts, and
+/// `margin_available = margin - mmf * |szi| * entry_px`. For isolated positions the margin is
+/// the initial margin `|szi| * entry_px / leverage`; for cross positions it is `account_value`,
+/// which should exclude the position's own unrealized pnl (`account_value - unrealizedPnl`
+/// from `user_state`) and `leverage` is unused. Returns None for an empty position or when
+/// the position cannot be liquidated.
+pub fn liquidation_price(
+    szi: f64,
+    entry_px: f64,
+    leverage: u32,
+    margin_type: MarginType,
+    account_value: f64,
+    mmf: f64,
+) -> Option<f64> {
+    let is_cross = margin_type.is_cross();
+    if szi == 0.0 || (!is_cross && leverage == 0) {
+        return None;
+    }
+    let side = szi.signum();
+    let sz = szi.abs();
+    let margin = if is_cross {
+        account_value
+    } else {
+        sz * entry_px / leverage as f64
+    };
+    let margin_available = margin - mmf * sz * entry_px;
+    let liquidation_px = entry_px - side * margin_available / sz / (1.0 - mmf * side);
+    (liquidation_px > 0.0).then_some(liquidation_px)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-2, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn maintenance_margin_from_max_leverage() {
+        let meta: Meta = serde_json::from_str(
+            r#"{"universe": [
+                {"name": "BTC", "szDecimals": 5, "maxLeverage": 50},
+                {"name": "OLD", "szDecimals": 0}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(meta.maintenance_margin("BTC"), Some(0.01));
+        assert_eq!(meta.maintenance_margin("OLD"), None);
+        assert_eq!(meta.maintenance_margin("ETH"), None);
+    }
+
+    #[test]
+    fn max_leverage_follows_margin_tiers() {
+        let meta: Meta = serde_json::from_str(
+            r#"{
+                "universe": [
+                    {"name": "BTC", "szDecimals": 5, "maxLeverage": 40, "marginTableId": 56},
+                    {"name": "SOL", "szDecimals": 2, "maxLeverage": 20, "marginTableId": 20},
+                    {"name": "OLD", "szDecimals": 0, "maxLeverage": 3}
+                ],
+                "marginTables": [[56, {"description": "tiered 40x", "marginTiers": [
+                    {"lowerBound": "0.0", "maxLeverage": 40},
+                    {"lowerBound": "150000000.0", "maxLeverage": 20}
+                ]}]]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(meta.max_leverage_at("BTC", 0.0), Some(40));
+        assert_eq!(meta.max_leverage_at("BTC", 149_999_999.0), Some(40));
+        assert_eq!(meta.max_leverage_at("BTC", 150_000_000.0), Some(20));
+        assert_eq!(meta.max_leverage_at("SOL", 1e12), Some(20));
+        assert_eq!(meta.max_leverage_at("OLD", 1.0), Some(3));
+        assert_eq!(meta.max_leverage_at("ETH", 1.0), None);
+    }
+
+    #[test]
+    fn liquidation_price_isolated() {
+        // long 1 @ 3000 at 10x: 300 margin, 30 maintenance
+        assert_close(
+            liquidation_price(1.0, 3000.0, 10, MarginType::Isolated, 0.0, 0.01),
+            2727.27,
+        );
+        assert_close(
+            liquidation_price(-1.0, 3000.0, 10, MarginType::Isolated, 0.0, 0.01),
+            3267.33,
+        );
+    }
+
+    #[test]
+    fn liquidation_price_cross() {
+        assert_close(
+            liquidation_price(1.0, 3000.0, 0, MarginType::Cross, 1000.0, 0.01),
+            2020.20,
+        );
+        assert_close(
+            liquidation_price(-2.0, 3000.0, 0, MarginType::Cross, 1000.0, 0.01),
+            3465.35,
+        );
+        // fully collateralized long can't be liquidated
+        assert_eq!(
+            liquidation_price(1.0, 3000.0, 0, MarginType::Cross, 5000.0, 0.01),
+            None
+        );
+        assert_eq!(
+            liquidation_price(0.0, 3000.0, 10, MarginType::Isolated, 0.0, 0.01),
+            None
+        );
+    }
+
+    #[test]
+    fn initial_margin_caps_leverage_at_tier() {
+        let meta: Meta = serde_json::from_str(
+            r#"{
+                "universe": [
+                    {"name": "BTC", "szDecimals": 5, "maxLeverage": 40, "marginTableId": 56}
+                ],
+                "marginTables": [[56, {"description": "tiered 40x", "marginTiers": [
+                    {"lowerBound": "0.0", "maxLeverage": 40},
+                    {"lowerBound": "100000.0", "maxLeverage": 10}
+                ]}]]
+            }"#,
+        )
+        .unwrap();
+        assert_close(meta.initial_margin("BTC", 0.1, 50000.0, 20), 250.0);
+        assert_close(meta.initial_margin("BTC", -0.1, 50000.0, 50), 125.0);
+        assert_close(meta.initial_margin("BTC", 4.0, 50000.0, 20), 20000.0);
+        assert_eq!(meta.initial_margin("BTC", 0.1, 50000.0, 0), None);
+        assert_eq!(meta.initial_margin("ETH", 1.0, 3000.0, 10), None);
+    }
+
+    #[test]
+    fn meta_keeps_unknown_fields() {
+        let meta: Meta = serde_json::from_str(
+            r#"{
+                "universe": [
+                    {"name": "BTC", "szDecimals": 5, "maxLeverage": 40, "isDelisted": true,
+                     "growthMode": {"enabled": false}, "marginMode": "strictIsolated"}
+                ],
+                "collateralToken": 0
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(meta.universe[0].sz_decimals, 5);
+        assert!(meta.universe[0].is_delisted);
+        assert_eq!(meta.universe[0].extra["growthMode"]["enabled"], false);
+        assert_eq!(meta.universe[0].extra["marginMode"], "strictIsolated");
+        assert_eq!(meta.extra["collateralToken"], 0);
+    }
+
+    #[test]
+    fn asset_status_reports_meta_flags() {
+        let meta: Meta = serde_json::from_str(
+            r#"{"universe": [
+                {"name": "BTC", "szDecimals": 5, "maxLeverage": 40},
+                {"name": "OLD", "szDecimals": 0, "isDelisted": true},
+                {"name": "NEW", "szDecimals": 0, "onlyIsolated": true}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(meta.asset_status("BTC").is_tradable());
+        assert!(!meta.asset_status("OLD").is_tradable());
+        let status = meta.asset_status("NEW");
+        assert!(status.is_tradable() && status.only_isolated);
+        let status = meta.asset_status("ETH");
+        assert!(!status.listed && !status.is_tradable());
+    }
 }
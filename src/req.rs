@@ -1,6 +1,12 @@
-use crate::{prelude::*, Error};
+use crate::{prelude::*, BaseUrl, Error};
+use async_trait::async_trait;
 use reqwest::{Client, Response};
 use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::Semaphore;
 
 #[derive(Deserialize, Debug)]
 struct ErrorData {
@@ -9,14 +15,29 @@ struct ErrorData {
     msg: String,
 }
 
+pub const DEFAULT_USER_AGENT: &str = concat!("hyperliquid-rust-sdk/", env!("CARGO_PKG_VERSION"));
+// High enough that only runaway fan-outs ever wait on it
+pub const DEFAULT_MAX_IN_FLIGHT_REQUESTS: usize = 256;
+
+#[async_trait]
+pub trait Transport: Send + Sync {
+    fn base_url(&self) -> &str;
+    async fn post(&self, url_path: &'static str, data: String) -> Result<String>;
+}
+
 pub struct HttpClient {
     pub client: Client,
     pub base_url: String,
+    // With the `tracing` feature, replaces signatures and addresses in logged bodies
+    pub redact_logs: bool,
+    // Each request holds a permit until its response body is read, capping how many requests
+    // are in flight at once. Clients can share one semaphore for a combined cap
+    pub in_flight: Arc<Semaphore>,
 }
 
 async fn parse_response(response: Response) -> Result<String> {
     let status_code = response.status().as_u16();
-    let headers = response.headers().clone();
+    let headers = Box::new(response.headers().clone());
     let text = response
         .text()
         .await
@@ -26,7 +47,7 @@ async fn parse_response(response: Response) -> Result<String> {
         return Ok(text);
     }
     let error_data = serde_json::from_str::<ErrorData>(&text);
-    if status_code >= 400 && status_code < 500 {
+    if (400..500).contains(&status_code) {
         let client_error = match error_data {
             Ok(error_data) => Error::ClientRequest {
                 status_code,
@@ -52,8 +73,57 @@ async fn parse_response(response: Response) -> Result<String> {
     })
 }
 
+#[async_trait]
+impl Transport for HttpClient {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+        self.send(url_path, data).await
+    }
+
+    #[cfg(feature = "tracing")]
+    async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+        use tracing::{field::Empty, Instrument};
+
+        let span = tracing::info_span!(
+            "hyperliquid_request",
+            endpoint = url_path,
+            payload_size = data.len(),
+            status_code = Empty,
+            latency_ms = Empty,
+        );
+        self.send(url_path, data).instrument(span).await
+    }
+}
+
 impl HttpClient {
-    pub async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+    // Client used when none is passed to InfoClient::new or ExchangeClient::new, identifying
+    // itself as `user_agent` or DEFAULT_USER_AGENT
+    pub fn build_client(user_agent: Option<&str>) -> Result<Client> {
+        Client::builder()
+            .user_agent(user_agent.unwrap_or(DEFAULT_USER_AGENT))
+            .build()
+            .map_err(|e| Error::GenericRequest(e.to_string()))
+    }
+
+    pub fn default_in_flight() -> Arc<Semaphore> {
+        Arc::new(Semaphore::new(DEFAULT_MAX_IN_FLIGHT_REQUESTS))
+    }
+
+    async fn send(&self, url_path: &'static str, data: String) -> Result<String> {
+        let _permit = self
+            .in_flight
+            .acquire()
+            .await
+            .map_err(|e| Error::GenericRequest(e.to_string()))?;
+        #[cfg(feature = "tracing")]
+        let started_at = std::time::Instant::now();
+        #[cfg(feature = "tracing")]
+        tracing::trace!(body = %log_body(&data, self.redact_logs), "request");
+
         let full_url = format!("{}{url_path}", self.base_url);
         let request = self
             .client
@@ -66,7 +136,241 @@ impl HttpClient {
             .client
             .execute(request)
             .await
+            .map_err(|e| Error::GenericRequest(e.to_string()));
+
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+            match &result {
+                Ok(response) => {
+                    span.record("status_code", response.status().as_u16());
+                }
+                Err(err) => tracing::warn!(%err, "request failed"),
+            }
+        }
+
+        let text = parse_response(result?).await;
+        #[cfg(feature = "tracing")]
+        match &text {
+            Ok(text) => tracing::trace!(body = %log_body(text, self.redact_logs), "response"),
+            Err(err) => tracing::warn!(%err, "request failed"),
+        }
+        text
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn log_body(body: &str, redact: bool) -> String {
+    if !redact {
+        return body.to_string();
+    }
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => "[redacted]".to_string(),
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn redact_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "signature" {
+                    *value = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_value),
+        serde_json::Value::String(s) => {
+            let is_address = s.len() == 42
+                && s.starts_with("0x")
+                && s[2..].chars().all(|c| c.is_ascii_hexdigit());
+            if is_address {
+                *s = "0x[redacted]".to_string();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Offline transport that answers every request with a canned JSON response.
+/// Responses are keyed by the `type` of the posted action (for `/exchange`) or of
+/// the info request (for `/info`), e.g. `"order"` or `"clearinghouseState"`.
+pub struct MockTransport {
+    base_url: String,
+    responses: HashMap<String, serde_json::Value>,
+    requests: Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+impl MockTransport {
+    pub fn new(base_url: BaseUrl) -> MockTransport {
+        MockTransport {
+            base_url: base_url.get_url(),
+            responses: HashMap::new(),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_response(mut self, request_type: &str, response: serde_json::Value) -> Self {
+        self.responses.insert(request_type.to_string(), response);
+        self
+    }
+
+    // Every request posted so far, as (url path, JSON body)
+    pub fn requests(&self) -> Vec<(String, serde_json::Value)> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+        let body: serde_json::Value =
+            serde_json::from_str(&data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let request_type = if url_path == "/exchange" {
+            &body["action"]["type"]
+        } else {
+            &body["type"]
+        }
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((url_path.to_string(), body));
+
+        let response = self.responses.get(&request_type).ok_or_else(|| {
+            Error::GenericRequest(format!("No mock response for request type {request_type}"))
+        })?;
+        serde_json::to_string(response).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    #[tokio::test]
+    async fn test_gzip_response_is_decompressed() -> Result<()> {
+        let listener =
+            TcpListener::bind("127.0.0.1:0").map_err(|e| Error::GenericRequest(e.to_string()))?;
+        let addr = listener
+            .local_addr()
             .map_err(|e| Error::GenericRequest(e.to_string()))?;
-        parse_response(result).await
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(r#"{"BTC": "65000.5", "ETH": "3000.25"}"#.as_bytes())
+                .unwrap();
+            let body = encoder.finish().unwrap();
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                 Content-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+
+        let http_client = HttpClient {
+            client: HttpClient::build_client(None)?,
+            base_url: format!("http://{addr}"),
+            redact_logs: false,
+            in_flight: HttpClient::default_in_flight(),
+        };
+        let response = http_client
+            .post("/info", r#"{"type": "allMids"}"#.to_string())
+            .await?;
+        let mids: HashMap<String, String> =
+            serde_json::from_str(&response).map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(mids["ETH"], "3000.25");
+
+        let request = server
+            .join()
+            .map_err(|_| Error::GenericRequest("server thread panicked".to_string()))?;
+        assert!(request.contains("accept-encoding: gzip"));
+        assert!(request.contains(&format!("user-agent: {DEFAULT_USER_AGENT}")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_requests_wait_for_an_in_flight_permit() -> Result<()> {
+        // Nothing listens on the port, so a request that gets a permit fails right away
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| Error::GenericRequest(e.to_string()))?;
+        let http_client = HttpClient {
+            client: HttpClient::build_client(None)?,
+            base_url: format!("http://{addr}"),
+            redact_logs: false,
+            in_flight: Arc::new(Semaphore::new(1)),
+        };
+
+        let permit = http_client.in_flight.clone().acquire_owned().await.unwrap();
+        let waiting = tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            http_client.post("/info", "{}".to_string()),
+        )
+        .await;
+        assert!(waiting.is_err());
+
+        drop(permit);
+        assert!(matches!(
+            http_client.post("/info", "{}".to_string()).await,
+            Err(Error::GenericRequest(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_log_body_redacts_signatures_and_addresses() {
+        let body = serde_json::json!({
+            "action": {
+                "type": "usdSend",
+                "destination": "0x0d1d9635d0640821d15e323ac8adadfa9c111414",
+                "amount": "1"
+            },
+            "signature": { "r": "0x01", "s": "0x02", "v": 27 },
+            "nonce": 1
+        })
+        .to_string();
+
+        assert_eq!(log_body(&body, false), body);
+        let redacted: serde_json::Value = serde_json::from_str(&log_body(&body, true)).unwrap();
+        assert_eq!(redacted["signature"], "[redacted]");
+        assert_eq!(redacted["action"]["destination"], "0x[redacted]");
+        assert_eq!(redacted["action"]["amount"], "1");
     }
 }
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Deserializer};
+use std::{fmt::Display, str::FromStr};
+
+// The API sends most amounts as strings but some as plain JSON numbers, and a field has been
+// known to switch between the two, so the numeric fields of the response structs take either
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(serde_json::Number),
+    String(String),
+}
+
+impl NumberOrString {
+    fn parse<T, E>(self) -> std::result::Result<T, E>
+    where
+        T: FromStr,
+        T::Err: Display,
+        E: serde::de::Error,
+    {
+        match self {
+            NumberOrString::Number(number) => number.to_string(),
+            NumberOrString::String(raw) => raw,
+        }
+        .parse()
+        .map_err(E::custom)
+    }
+}
+
+pub(crate) fn number_or_string_f64<'de, D>(deserializer: D) -> std::result::Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    number_or_string(deserializer)
+}
+
+// Absent or null becomes None
+pub(crate) fn option_number_or_string_f64<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<NumberOrString>::deserialize(deserializer)?
+        .map(NumberOrString::parse)
+        .transpose()
+}
+
+// For `Number` fields, which are a decimal with the `decimal` feature
+pub(crate) fn number_or_string<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    NumberOrString::deserialize(deserializer)?.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Amounts {
+        #[serde(deserialize_with = "number_or_string_f64")]
+        px: f64,
+        #[serde(default, deserialize_with = "option_number_or_string_f64")]
+        fee: Option<f64>,
+    }
+
+    #[test]
+    fn numbers_parse_from_strings_and_numbers() {
+        let parse = |value| serde_json::from_value::<Amounts>(value).unwrap();
+        let expected = Amounts {
+            px: 1.5,
+            fee: Some(0.25),
+        };
+        assert_eq!(parse(json!({ "px": "1.5", "fee": "0.25" })), expected);
+        assert_eq!(parse(json!({ "px": 1.5, "fee": 0.25 })), expected);
+        assert_eq!(parse(json!({ "px": 2, "fee": null })).fee, None);
+        assert_eq!(parse(json!({ "px": "2" })).px, 2.0);
+
+        assert!(serde_json::from_value::<Amounts>(json!({ "px": "abc" })).is_err());
+        assert!(serde_json::from_value::<Amounts>(json!({ "px": true })).is_err());
+    }
+}
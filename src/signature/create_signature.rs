@@ -2,7 +2,10 @@ use ethers::{
     abi::AbiEncode,
     core::k256::{elliptic_curve::FieldBytes, Secp256k1},
     signers::LocalWallet,
-    types::{transaction::eip712::Eip712, Signature, H256, U256},
+    types::{
+        transaction::eip712::{EIP712Domain, Eip712},
+        Signature, H160, H256, U256,
+    },
     utils::keccak256,
 };
 
@@ -93,7 +96,18 @@ pub(crate) fn sign_with_agent(
     }
 }
 
-fn sign_typed_data<T: Eip712>(payload: &T, wallet: &LocalWallet) -> Result<Signature> {
+// Domain shared by all actions signed directly by the user rather than through the l1 agent
+pub(crate) fn user_signed_domain(chain_id: U256) -> EIP712Domain {
+    EIP712Domain {
+        name: Some("HyperliquidSignTransaction".to_string()),
+        version: Some("1".to_string()),
+        chain_id: Some(chain_id),
+        verifying_contract: Some(H160::zero()),
+        salt: None,
+    }
+}
+
+pub(crate) fn sign_typed_data<T: Eip712>(payload: &T, wallet: &LocalWallet) -> Result<Signature> {
     let encoded = payload
         .encode_eip712()
         .map_err(|e| Error::Eip712(e.to_string()))?;
@@ -3,5 +3,6 @@ mod create_signature;
 pub(crate) mod usdc_transfer;
 
 pub(crate) use create_signature::{
-    keccak, sign_l1_action, sign_usd_transfer_action, sign_with_agent,
+    keccak, sign_l1_action, sign_typed_data, sign_usd_transfer_action, sign_with_agent,
+    user_signed_domain,
 };
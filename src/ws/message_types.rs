@@ -11,6 +11,39 @@ pub struct L2Book {
     pub data: L2BookData,
 }
 
+impl L2Book {
+    pub fn spread(&self) -> Option<f64> {
+        self.data.spread()
+    }
+
+    pub fn mid(&self) -> Option<f64> {
+        self.data.mid()
+    }
+
+    pub fn imbalance(&self, depth: usize) -> f64 {
+        self.data.imbalance(depth)
+    }
+
+    pub fn vwap_for_size(&self, is_buy: bool, sz: f64) -> Option<(f64, f64)> {
+        self.data.vwap_for_size(is_buy, sz)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Post {
+    pub data: PostData,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SubscriptionResponse {
+    pub data: SubscriptionResponseData,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WsError {
+    pub data: String,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct AllMids {
     pub data: AllMidsData,
@@ -26,6 +59,11 @@ pub struct UserFills {
     pub data: UserFillsData,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct UserTwapSliceFills {
+    pub data: UserTwapSliceFillsData,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Candle {
     pub data: CandleData,
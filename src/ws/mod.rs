@@ -1,7 +1,10 @@
 mod message_types;
+mod order_book;
 mod sub_structs;
+mod volume_bars;
 mod ws_manager;
 pub use message_types::*;
+pub use order_book::{OrderBook, OrderBookLevel};
 pub use sub_structs::*;
-pub(crate) use ws_manager::WsManager;
-pub use ws_manager::{Message, Subscription};
+pub use volume_bars::{VolumeBar, VolumeBarAggregator};
+pub use ws_manager::{Message, Subscription, WsManager};
@@ -0,0 +1,156 @@
+use crate::{info::L2SnapshotResponse, prelude::*, ws::message_types::L2Book, Error};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrderBookLevel {
+    pub px: f64,
+    pub sz: f64,
+    // Number of orders resting at `px`
+    pub n: u64,
+}
+
+// A local book for one coin, seeded from an http l2Book snapshot and kept current with the
+// websocket l2Book updates. Every update is a full snapshot of the top levels, so applying one
+// replaces the book rather than patching it, and the server sends no checksum to verify
+// against. Updates older than the book, or for another coin, are ignored.
+#[derive(Clone, Debug)]
+pub struct OrderBook {
+    pub coin: String,
+    pub time: u64,
+    // Sorted best first
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+}
+
+fn parse_level(px: &str, sz: &str, n: u64) -> Result<OrderBookLevel> {
+    Ok(OrderBookLevel {
+        px: px.parse().map_err(|_| Error::FloatStringParse)?,
+        sz: sz.parse().map_err(|_| Error::FloatStringParse)?,
+        n,
+    })
+}
+
+impl OrderBook {
+    pub fn from_snapshot(snapshot: &L2SnapshotResponse) -> Result<OrderBook> {
+        let side = |index: usize| {
+            snapshot
+                .levels
+                .get(index)
+                .into_iter()
+                .flatten()
+                .map(|level| parse_level(&level.px, &level.sz, level.n))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(OrderBook {
+            coin: snapshot.coin.clone(),
+            time: snapshot.time,
+            bids: side(0)?,
+            asks: side(1)?,
+        })
+    }
+
+    // Returns whether the update was applied
+    pub fn apply(&mut self, update: &L2Book) -> Result<bool> {
+        let data = &update.data;
+        if data.coin != self.coin || data.time < self.time {
+            return Ok(false);
+        }
+        let side = |index: usize| {
+            data.levels
+                .get(index)
+                .into_iter()
+                .flatten()
+                .map(|level| parse_level(&level.px, &level.sz, level.n))
+                .collect::<Result<Vec<_>>>()
+        };
+        // Parse both sides first so a bad update leaves the book untouched
+        let (bids, asks) = (side(0)?, side(1)?);
+        self.time = data.time;
+        self.bids = bids;
+        self.asks = asks;
+        Ok(true)
+    }
+
+    pub fn best_bid(&self) -> Option<&OrderBookLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&OrderBookLevel> {
+        self.asks.first()
+    }
+
+    // The top `n` (bids, asks)
+    pub fn depth(&self, n: usize) -> (&[OrderBookLevel], &[OrderBookLevel]) {
+        (
+            &self.bids[..n.min(self.bids.len())],
+            &self.asks[..n.min(self.asks.len())],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{info::Level, BookLevel, L2BookData};
+
+    fn update(coin: &str, time: u64, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> L2Book {
+        let side = |levels: &[(&str, &str)]| {
+            levels
+                .iter()
+                .map(|(px, sz)| BookLevel {
+                    px: px.to_string(),
+                    sz: sz.to_string(),
+                    n: 1,
+                })
+                .collect()
+        };
+        L2Book {
+            data: L2BookData {
+                coin: coin.to_string(),
+                time,
+                levels: vec![side(bids), side(asks)],
+            },
+        }
+    }
+
+    #[test]
+    fn book_follows_newer_updates() -> Result<()> {
+        let level = |px: &str, sz: &str| Level {
+            n: 2,
+            px: px.to_string(),
+            sz: sz.to_string(),
+        };
+        let mut book = OrderBook::from_snapshot(&L2SnapshotResponse {
+            coin: "ETH".to_string(),
+            levels: vec![
+                vec![level("1799.5", "2.0"), level("1799.0", "1.0")],
+                vec![level("1800.5", "3.0")],
+            ],
+            time: 100,
+        })?;
+        assert_eq!(
+            book.best_bid(),
+            Some(&OrderBookLevel {
+                px: 1799.5,
+                sz: 2.0,
+                n: 2
+            })
+        );
+        assert_eq!(book.depth(5).0.len(), 2);
+        assert_eq!(book.depth(1).1[0].px, 1800.5);
+
+        assert!(!book.apply(&update("ETH", 99, &[("1.0", "1.0")], &[]))?);
+        assert!(!book.apply(&update("BTC", 200, &[("1.0", "1.0")], &[]))?);
+        assert_eq!(book.time, 100);
+
+        assert!(book.apply(&update("ETH", 101, &[("1799.0", "4.0")], &[]))?);
+        assert_eq!(book.best_bid().map(|level| level.sz), Some(4.0));
+        assert_eq!(book.best_ask(), None);
+
+        assert!(matches!(
+            book.apply(&update("ETH", 102, &[("abc", "1.0")], &[])),
+            Err(Error::FloatStringParse)
+        ));
+        assert_eq!(book.time, 101);
+        Ok(())
+    }
+}
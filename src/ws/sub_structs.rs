@@ -1,3 +1,10 @@
+use crate::{
+    consts::EPSILON,
+    helpers::{parse_number, Number},
+    prelude::*,
+    serde_helpers::option_number_or_string_f64,
+    Side,
+};
 use ethers::types::H160;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -5,7 +12,7 @@ use std::collections::HashMap;
 #[derive(Deserialize, Clone, Debug)]
 pub struct Trade {
     pub coin: String,
-    pub side: String,
+    pub side: Side,
     pub px: String,
     pub sz: String,
     pub time: u64,
@@ -19,6 +26,10 @@ pub struct BookLevel {
     pub n: u64,
 }
 
+// Every l2Book message is a full snapshot of the top levels rather than a diff, and the
+// server sends no checksum, so there is no incremental state that could drift. Gaps from
+// reconnects are covered by `WsManager::with_l2_book_resnapshot`. See `OrderBook` for a
+// maintained local book.
 #[derive(Deserialize, Clone, Debug)]
 pub struct L2BookData {
     pub coin: String,
@@ -26,16 +37,113 @@ pub struct L2BookData {
     pub levels: Vec<Vec<BookLevel>>,
 }
 
+impl BookLevel {
+    fn px(&self) -> Option<f64> {
+        self.px.parse().ok()
+    }
+
+    fn sz(&self) -> f64 {
+        self.sz.parse().unwrap_or_default()
+    }
+}
+
+impl L2BookData {
+    // levels[0] holds the bids and levels[1] the asks, each sorted best first
+    fn side(&self, index: usize) -> &[BookLevel] {
+        self.levels
+            .get(index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn best_bid(&self) -> Option<f64> {
+        self.side(0).first().and_then(BookLevel::px)
+    }
+
+    pub fn best_ask(&self) -> Option<f64> {
+        self.side(1).first().and_then(BookLevel::px)
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()? - self.best_bid()?)
+    }
+
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_ask()? + self.best_bid()?) / 2.0)
+    }
+
+    /// Returns (bid size - ask size) / (bid size + ask size) over the top `depth` levels
+    /// of each side, in [-1, 1]. A one-sided book gives 1 or -1, an empty book 0.
+    pub fn imbalance(&self, depth: usize) -> f64 {
+        let bid_sz: f64 = self.side(0).iter().take(depth).map(BookLevel::sz).sum();
+        let ask_sz: f64 = self.side(1).iter().take(depth).map(BookLevel::sz).sum();
+        let total = bid_sz + ask_sz;
+        if total > 0.0 {
+            (bid_sz - ask_sz) / total
+        } else {
+            0.0
+        }
+    }
+
+    /// Walks the asks (`is_buy`) or the bids to fill `sz`, returning the volume weighted
+    /// average price and the worst price touched. None when the side is too thin for `sz`.
+    pub fn vwap_for_size(&self, is_buy: bool, sz: f64) -> Option<(f64, f64)> {
+        if sz <= 0.0 {
+            return None;
+        }
+        let mut remaining = sz;
+        let mut notional = 0.0;
+        for level in self.side(if is_buy { 1 } else { 0 }) {
+            let px = level.px()?;
+            let fill = remaining.min(level.sz());
+            notional += fill * px;
+            remaining -= fill;
+            if remaining < EPSILON {
+                return Some((notional / sz, px));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PostData {
+    pub id: u64,
+    pub response: PostResponse,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", content = "payload")]
+#[serde(rename_all = "camelCase")]
+pub enum PostResponse {
+    Info(serde_json::Value),
+    Action(serde_json::Value),
+    Error(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SubscriptionResponseData {
+    pub method: String,
+    pub subscription: serde_json::Value,
+}
+
+// Every message carries the mid of every coin, perps by name and spot pairs by "@index"
 #[derive(Deserialize, Clone, Debug)]
 pub struct AllMidsData {
     pub mids: HashMap<String, String>,
 }
 
+impl AllMidsData {
+    pub fn mid(&self, coin: &str) -> Option<f64> {
+        self.mids.get(coin)?.parse().ok()
+    }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TradeInfo {
     pub coin: String,
-    pub side: String,
+    pub side: Side,
     pub px: String,
     pub sz: String,
     pub time: u64,
@@ -47,6 +155,28 @@ pub struct TradeInfo {
     pub cloid: Option<String>,
     pub crossed: bool,
     pub fee: String,
+    #[serde(default)]
+    pub tid: u64,
+    // Charged on top of `fee` when the order was routed through a builder
+    #[serde(default, deserialize_with = "option_number_or_string_f64")]
+    pub builder_fee: Option<f64>,
+}
+
+impl TradeInfo {
+    // `crossed` marks the taker side of the trade
+    pub fn is_maker(&self) -> bool {
+        !self.crossed
+    }
+
+    // Signed position size before this fill
+    pub fn start_position_number(&self) -> Result<Number> {
+        parse_number(&self.start_position)
+    }
+
+    // Realized pnl of the part of the fill that reduced a position, excluding fees
+    pub fn closed_pnl_number(&self) -> Result<Number> {
+        parse_number(&self.closed_pnl)
+    }
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -55,6 +185,30 @@ pub struct UserFillsData {
     pub fills: Vec<TradeInfo>,
 }
 
+impl UserFillsData {
+    pub fn total_builder_fees(&self) -> f64 {
+        self.fills.iter().filter_map(|fill| fill.builder_fee).sum()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapSliceFill {
+    pub fill: TradeInfo,
+    pub twap_id: u64,
+}
+
+// The first message after subscribing is a snapshot of recent slice fills, later ones only
+// carry new fills
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserTwapSliceFillsData {
+    #[serde(default)]
+    pub is_snapshot: bool,
+    pub user: H160,
+    pub twap_slice_fills: Vec<TwapSliceFill>,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct UserData {
     pub fills: Vec<TradeInfo>,
@@ -92,11 +246,18 @@ pub struct OrderUpdate {
     pub status_timestamp: u64,
 }
 
+impl OrderUpdate {
+    // Filled, canceled for any reason or rejected, i.e. the order can't change anymore
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self.status.as_str(), "open" | "triggered")
+    }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct BasicOrder {
     pub coin: String,
-    pub side: String,
+    pub side: Side,
     pub limit_px: String,
     pub sz: String,
     pub oid: u64,
@@ -104,3 +265,103 @@ pub struct BasicOrder {
     pub orig_sz: String,
     pub cloid: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(px: &str, sz: &str) -> BookLevel {
+        BookLevel {
+            px: px.to_string(),
+            sz: sz.to_string(),
+            n: 1,
+        }
+    }
+
+    fn book(bids: Vec<BookLevel>, asks: Vec<BookLevel>) -> L2BookData {
+        L2BookData {
+            coin: "ETH".to_string(),
+            time: 0,
+            levels: vec![bids, asks],
+        }
+    }
+
+    #[test]
+    fn spread_and_mid_use_top_of_book() {
+        let book = book(
+            vec![level("100.0", "1"), level("99.0", "5")],
+            vec![level("101.0", "2"), level("102.0", "5")],
+        );
+        assert_eq!(book.spread(), Some(1.0));
+        assert_eq!(book.mid(), Some(100.5));
+    }
+
+    #[test]
+    fn one_sided_book_has_no_spread_or_mid() {
+        let bids_only = book(vec![level("100.0", "1")], vec![]);
+        assert_eq!(bids_only.spread(), None);
+        assert_eq!(bids_only.mid(), None);
+        assert_eq!(bids_only.imbalance(5), 1.0);
+
+        let asks_only = book(vec![], vec![level("101.0", "1")]);
+        assert_eq!(asks_only.mid(), None);
+        assert_eq!(asks_only.imbalance(5), -1.0);
+
+        let empty = L2BookData {
+            coin: "ETH".to_string(),
+            time: 0,
+            levels: vec![],
+        };
+        assert_eq!(empty.spread(), None);
+        assert_eq!(empty.imbalance(5), 0.0);
+    }
+
+    #[test]
+    fn imbalance_only_counts_top_levels() {
+        let book = book(
+            vec![level("100.0", "3"), level("99.0", "100")],
+            vec![level("101.0", "1"), level("102.0", "1")],
+        );
+        assert_eq!(book.imbalance(0), 0.0);
+        assert_eq!(book.imbalance(1), 0.5);
+        assert_eq!(book.imbalance(2), 101.0 / 105.0);
+        assert_eq!(book.imbalance(10), book.imbalance(2));
+    }
+
+    #[test]
+    fn vwap_walks_the_book() {
+        // 0.1 + 0.2 doesn't add up to exactly 0.3
+        let thin = book(vec![], vec![level("1.0", "0.1"), level("2.0", "0.2")]);
+        assert!(thin.vwap_for_size(true, 0.3).is_some());
+
+        let book = book(
+            vec![level("100.0", "1"), level("99.0", "3")],
+            vec![level("101.0", "2"), level("103.0", "2")],
+        );
+        assert_eq!(book.vwap_for_size(true, 1.0), Some((101.0, 101.0)));
+        assert_eq!(book.vwap_for_size(true, 4.0), Some((102.0, 103.0)));
+        assert_eq!(book.vwap_for_size(false, 2.0), Some((99.5, 99.0)));
+    }
+
+    #[test]
+    fn vwap_needs_enough_depth() {
+        let book = book(vec![level("100.0", "1")], vec![]);
+        assert_eq!(book.vwap_for_size(false, 1.5), None);
+        assert_eq!(book.vwap_for_size(true, 0.1), None);
+        assert_eq!(book.vwap_for_size(false, 0.0), None);
+    }
+
+    #[test]
+    fn fill_accessors() -> Result<()> {
+        let fill: TradeInfo = serde_json::from_value(serde_json::json!({
+            "coin": "ETH", "side": "A", "px": "1800.0", "sz": "0.5", "time": 1,
+            "hash": "0x00", "startPosition": "1.5", "dir": "Close Long",
+            "closedPnl": "-12.25", "oid": 1, "cloid": null, "crossed": false, "fee": "-0.01"
+        }))
+        .unwrap();
+        assert!(fill.is_maker());
+        assert_eq!(fill.start_position_number()?, parse_number("1.5")?);
+        assert_eq!(fill.closed_pnl_number()?, parse_number("-12.25")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{prelude::*, ws::message_types::Trades, Error, Trade};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolumeBar {
+    pub coin: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    // Times of the first and last trade in the bar, in milliseconds
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+// Groups trades into bars that close once their volume reaches `threshold`. A trade is never
+// split between bars, so a bar's volume can overshoot the threshold by up to one trade.
+// Coins are aggregated independently, so one aggregator can consume several trade streams.
+#[derive(Debug)]
+pub struct VolumeBarAggregator {
+    pub threshold: f64,
+    open_bars: HashMap<String, VolumeBar>,
+}
+
+impl VolumeBarAggregator {
+    pub fn new(threshold: f64) -> VolumeBarAggregator {
+        VolumeBarAggregator {
+            threshold,
+            open_bars: HashMap::new(),
+        }
+    }
+
+    // Returns the bar `trade` completed, if any
+    pub fn push(&mut self, trade: &Trade) -> Result<Option<VolumeBar>> {
+        let px: f64 = trade.px.parse().map_err(|_| Error::FloatStringParse)?;
+        let sz: f64 = trade.sz.parse().map_err(|_| Error::FloatStringParse)?;
+
+        let bar = self
+            .open_bars
+            .entry(trade.coin.clone())
+            .or_insert_with(|| VolumeBar {
+                coin: trade.coin.clone(),
+                open: px,
+                high: px,
+                low: px,
+                close: px,
+                volume: 0.0,
+                start_time: trade.time,
+                end_time: trade.time,
+            });
+        bar.high = bar.high.max(px);
+        bar.low = bar.low.min(px);
+        bar.close = px;
+        bar.volume += sz;
+        bar.end_time = trade.time;
+
+        if bar.volume >= self.threshold {
+            Ok(self.open_bars.remove(&trade.coin))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Feeds every trade of a `Message::Trades` update, returning the bars they completed
+    pub fn push_trades(&mut self, trades: &Trades) -> Result<Vec<VolumeBar>> {
+        let mut bars = Vec::new();
+        for trade in &trades.data {
+            if let Some(bar) = self.push(trade)? {
+                bars.push(bar);
+            }
+        }
+        Ok(bars)
+    }
+
+    // The bar still accumulating for `coin`
+    pub fn open_bar(&self, coin: &str) -> Option<&VolumeBar> {
+        self.open_bars.get(coin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Side;
+
+    fn trade(coin: &str, px: &str, sz: &str, time: u64) -> Trade {
+        Trade {
+            coin: coin.to_string(),
+            side: Side::Bid,
+            px: px.to_string(),
+            sz: sz.to_string(),
+            time,
+            hash: String::default(),
+        }
+    }
+
+    #[test]
+    fn bars_close_when_volume_crosses_threshold() -> Result<()> {
+        let mut aggregator = VolumeBarAggregator::new(2.0);
+        let trades = Trades {
+            data: vec![
+                trade("ETH", "100", "0.5", 1),
+                trade("BTC", "50000", "1.0", 2),
+                trade("ETH", "103", "1.0", 3),
+                trade("ETH", "99", "1.0", 4),
+                trade("ETH", "101", "0.25", 5),
+            ],
+        };
+
+        let bars = aggregator.push_trades(&trades)?;
+        assert_eq!(
+            bars,
+            vec![VolumeBar {
+                coin: "ETH".to_string(),
+                open: 100.0,
+                high: 103.0,
+                low: 99.0,
+                close: 99.0,
+                volume: 2.5,
+                start_time: 1,
+                end_time: 4,
+            }]
+        );
+        assert_eq!(aggregator.open_bar("ETH").map(|bar| bar.volume), Some(0.25));
+        assert_eq!(
+            aggregator.open_bar("BTC").map(|bar| bar.open),
+            Some(50000.0)
+        );
+
+        assert!(matches!(
+            aggregator.push(&trade("ETH", "abc", "1.0", 6)),
+            Err(Error::FloatStringParse)
+        ));
+        Ok(())
+    }
+}
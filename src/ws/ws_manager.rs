@@ -1,16 +1,37 @@
 use crate::{
+    helpers::now_timestamp_ms,
     prelude::*,
-    ws::message_types::{AllMids, Candle, L2Book, OrderUpdates, Trades, User},
-    Error, UserFills,
+    req::Transport,
+    ws::{
+        message_types::{
+            AllMids, Candle, L2Book, OrderUpdates, Post, SubscriptionResponse, Trades, User,
+            UserTwapSliceFills, WsError,
+        },
+        L2BookData, PostResponse, TradeInfo, UserFillsData,
+    },
+    Error, ExchangeResponseStatus, Interval, UserFills,
 };
-use futures_util::{stream::SplitSink, SinkExt, StreamExt};
-use log::error;
+use ethers::types::Signature;
+use futures_util::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use log::{error, info};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 use tokio::{
     net::TcpStream,
-    spawn,
-    sync::{mpsc::UnboundedSender, Mutex},
+    select, spawn,
+    sync::{mpsc::UnboundedSender, oneshot, Mutex},
+    task::JoinHandle,
+    time::{interval, sleep, timeout},
 };
 use tokio_tungstenite::{
     connect_async,
@@ -20,14 +41,80 @@ use tokio_tungstenite::{
 
 use ethers::types::H160;
 
+// Hyperliquid closes connections that have been idle for a minute
+const PING_INTERVAL: Duration = Duration::from_secs(50);
+const PONG_TIMEOUT: Duration = Duration::from_secs(10);
+const HEARTBEAT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const POST_TIMEOUT: Duration = Duration::from_secs(30);
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Writer = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, protocol::Message>;
+type Reader = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
+type PendingPosts = Arc<Mutex<HashMap<u64, oneshot::Sender<PostResponse>>>>;
+// Keyed on the subscription identifier, resolved by the server's subscriptionResponse ack
+// or with the error message when it rejects the subscription
+type PendingSubscribes =
+    Arc<Mutex<HashMap<String, oneshot::Sender<std::result::Result<(), String>>>>>;
+// Latest fill time delivered per user, and the trade ids delivered at exactly that time
+type FillCursors = Arc<Mutex<HashMap<H160, FillCursor>>>;
+type RawSubscriptions = Arc<Mutex<HashMap<String, Vec<RawSubscriptionData>>>>;
+
 #[derive(Debug)]
 struct SubscriptionData {
     sending_channel: UnboundedSender<Message>,
     subscription_id: u32,
+    // Subscription sent to the server, replayed after reconnecting
+    identifier: String,
+}
+
+#[derive(Debug)]
+struct RawSubscriptionData {
+    sending_channel: UnboundedSender<serde_json::Value>,
+    subscription_id: u32,
+    // Messages are forwarded by their channel, which is the subscription type
+    channel: String,
+}
+
+#[derive(Debug, Default)]
+struct FillCursor {
+    time: u64,
+    tids: HashSet<u64>,
+}
+
+impl FillCursor {
+    fn is_new(&self, fill: &TradeInfo) -> bool {
+        fill.time > self.time || (fill.time == self.time && !self.tids.contains(&fill.tid))
+    }
+
+    fn advance(&mut self, fill: &TradeInfo) {
+        if fill.time > self.time {
+            self.time = fill.time;
+            self.tids.clear();
+        }
+        if fill.time == self.time {
+            self.tids.insert(fill.tid);
+        }
+    }
 }
-pub(crate) struct WsManager {
-    writer: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, protocol::Message>,
+
+#[derive(Default)]
+struct Heartbeat {
+    ping_sent_at: Option<Instant>,
+    last_ping_at: Option<Instant>,
+    latency: Option<Duration>,
+}
+
+pub struct WsManager {
+    writer: Arc<Mutex<Writer>>,
     subscriptions: Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+    raw_subscriptions: RawSubscriptions,
+    heartbeat: Arc<Mutex<Heartbeat>>,
+    pending_posts: PendingPosts,
+    pending_subscribes: PendingSubscribes,
+    fill_cursors: FillCursors,
+    post_id: AtomicU64,
+    reader_handle: JoinHandle<()>,
     subscription_id: u32,
     subscription_identifiers: HashMap<u32, String>,
 }
@@ -41,7 +128,8 @@ pub enum Subscription {
     L2Book { coin: String },
     UserEvents { user: H160 },
     UserFills { user: H160 },
-    Candle { coin: String, interval: String },
+    UserTwapSliceFills { user: H160 },
+    Candle { coin: String, interval: Interval },
     OrderUpdates { user: H160 },
 }
 
@@ -54,9 +142,13 @@ pub enum Message {
     L2Book(L2Book),
     User(User),
     UserFills(UserFills),
+    UserTwapSliceFills(UserTwapSliceFills),
     Candle(Candle),
-    SubscriptionResponse,
+    SubscriptionResponse(SubscriptionResponse),
     OrderUpdates(OrderUpdates),
+    Post(Post),
+    Error(WsError),
+    Pong,
 }
 
 #[derive(Serialize)]
@@ -65,43 +157,433 @@ pub(crate) struct SubscriptionSendData<'a> {
     subscription: &'a serde_json::Value,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PostActionPayload {
+    action: serde_json::Value,
+    signature: Signature,
+    nonce: u64,
+    vault_address: Option<H160>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", content = "payload")]
+#[serde(rename_all = "camelCase")]
+enum PostRequest {
+    Info(serde_json::Value),
+    Action(PostActionPayload),
+}
+
+#[derive(Serialize)]
+struct PostSendData {
+    method: &'static str,
+    id: u64,
+    request: PostRequest,
+}
+
 impl WsManager {
-    pub(crate) async fn new(url: String) -> Result<WsManager> {
+    pub async fn new(url: String) -> Result<WsManager> {
+        WsManager::connect(url, None, false).await
+    }
+
+    // After every reconnect, userFills subscribers first receive the fills made while the
+    // connection was down, fetched over http from the last fill they were sent
+    pub async fn with_transport(url: String, transport: Arc<dyn Transport>) -> Result<WsManager> {
+        WsManager::connect(url, Some(transport), false).await
+    }
+
+    // Like `with_transport`, and l2Book subscribers also receive an http snapshot of their book
+    // before live updates resume, so nothing missed during the gap goes unnoticed
+    pub async fn with_l2_book_resnapshot(
+        url: String,
+        transport: Arc<dyn Transport>,
+    ) -> Result<WsManager> {
+        WsManager::connect(url, Some(transport), true).await
+    }
+
+    async fn connect(
+        url: String,
+        snapshot_transport: Option<Arc<dyn Transport>>,
+        l2_book_resnapshot: bool,
+    ) -> Result<WsManager> {
         let (ws_stream, _) = connect_async(url.clone())
             .await
             .map_err(|e| Error::Websocket(e.to_string()))?;
 
         let (writer, mut reader) = ws_stream.split();
+        let writer = Arc::new(Mutex::new(writer));
+        let writer_copy = Arc::clone(&writer);
 
         let subscriptions_map: HashMap<String, Vec<SubscriptionData>> = HashMap::new();
         let subscriptions = Arc::new(Mutex::new(subscriptions_map));
         let subscriptions_copy = Arc::clone(&subscriptions);
 
+        let raw_subscriptions: RawSubscriptions = Arc::new(Mutex::new(HashMap::new()));
+        let raw_subscriptions_copy = Arc::clone(&raw_subscriptions);
+
+        let heartbeat = Arc::new(Mutex::new(Heartbeat::default()));
+        let heartbeat_copy = Arc::clone(&heartbeat);
+
+        let pending_posts: PendingPosts = Arc::new(Mutex::new(HashMap::new()));
+        let pending_posts_copy = Arc::clone(&pending_posts);
+
+        let pending_subscribes: PendingSubscribes = Arc::new(Mutex::new(HashMap::new()));
+        let pending_subscribes_copy = Arc::clone(&pending_subscribes);
+
+        let fill_cursors: FillCursors = Arc::new(Mutex::new(HashMap::new()));
+        let fill_cursors_copy = Arc::clone(&fill_cursors);
+
         let reader_fut = async move {
-            // TODO: reconnect
+            let mut heartbeat_check = interval(HEARTBEAT_CHECK_INTERVAL);
             loop {
-                let data = reader.next().await;
-                if let Err(err) = WsManager::parse_and_send_data(data, &subscriptions_copy).await {
-                    error!("Error processing data received by WS manager reader: {err}");
+                let reconnect = select! {
+                    data = reader.next() => {
+                        WsManager::handle_data(
+                            data,
+                            &subscriptions_copy,
+                            &raw_subscriptions_copy,
+                            &heartbeat_copy,
+                            &pending_posts_copy,
+                            &pending_subscribes_copy,
+                            &fill_cursors_copy,
+                        )
+                        .await
+                    }
+                    _ = heartbeat_check.tick() => {
+                        match WsManager::check_heartbeat(&writer_copy, &heartbeat_copy).await {
+                            Ok(alive) => !alive,
+                            Err(err) => {
+                                error!("Error sending ping from WS manager: {err}");
+                                true
+                            }
+                        }
+                    }
+                };
+                if reconnect {
+                    reader = WsManager::reconnect(
+                        &url,
+                        &writer_copy,
+                        &subscriptions_copy,
+                        &raw_subscriptions_copy,
+                        &heartbeat_copy,
+                    )
+                    .await;
+                    if let Some(transport) = &snapshot_transport {
+                        if let Err(err) = WsManager::backfill_user_fills(
+                            transport,
+                            &subscriptions_copy,
+                            &fill_cursors_copy,
+                        )
+                        .await
+                        {
+                            error!(
+                                "Error backfilling user fills after WS manager reconnect: {err}"
+                            );
+                        }
+                        if l2_book_resnapshot {
+                            if let Err(err) =
+                                WsManager::resnapshot_l2_books(transport, &subscriptions_copy).await
+                            {
+                                error!(
+                                    "Error resnapshotting l2 books after WS manager reconnect: {err}"
+                                );
+                            }
+                        }
+                    }
                 }
             }
         };
-        spawn(reader_fut);
+        let reader_handle = spawn(reader_fut);
 
         Ok(WsManager {
             writer,
             subscriptions,
+            raw_subscriptions,
+            heartbeat,
+            pending_posts,
+            pending_subscribes,
+            fill_cursors,
+            post_id: AtomicU64::new(0),
+            reader_handle,
             subscription_id: 0,
             subscription_identifiers: HashMap::new(),
         })
     }
 
+    // Returns true when the connection is gone and needs to be reestablished
+    async fn handle_data(
+        data: Option<std::result::Result<protocol::Message, tungstenite::Error>>,
+        subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+        raw_subscriptions: &RawSubscriptions,
+        heartbeat: &Arc<Mutex<Heartbeat>>,
+        pending_posts: &PendingPosts,
+        pending_subscribes: &PendingSubscribes,
+        fill_cursors: &FillCursors,
+    ) -> bool {
+        match data {
+            Some(Ok(data)) => {
+                if let Err(err) = WsManager::parse_and_send_data(
+                    data,
+                    subscriptions,
+                    raw_subscriptions,
+                    heartbeat,
+                    pending_posts,
+                    pending_subscribes,
+                    fill_cursors,
+                )
+                .await
+                {
+                    error!("Error processing data received by WS manager reader: {err}");
+                }
+                false
+            }
+            Some(Err(err)) => {
+                error!("WS manager reader error: {err}");
+                true
+            }
+            None => {
+                error!("WS manager connection closed");
+                true
+            }
+        }
+    }
+
+    /// Sends a close frame, stops the background reader (and with it any reconnect attempt)
+    /// and drops every subscriber channel, so receivers see their stream end. Pending post
+    /// requests fail. The manager is consumed and cannot be used afterwards; dropping it
+    /// without calling `close` also stops the reader, but skips the close frame.
+    pub async fn close(self) -> Result<()> {
+        self.reader_handle.abort();
+        self.subscriptions.lock().await.clear();
+        self.raw_subscriptions.lock().await.clear();
+        self.pending_posts.lock().await.clear();
+        self.pending_subscribes.lock().await.clear();
+
+        let mut writer = self.writer.lock().await;
+        writer
+            .send(protocol::Message::Close(None))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))?;
+        writer
+            .close()
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))
+    }
+
+    // Round trip time of the most recent ping, None until the first pong arrives
+    pub(crate) async fn latency(&self) -> Option<Duration> {
+        self.heartbeat.lock().await.latency
+    }
+
+    // Returns false when the last ping went unanswered for longer than PONG_TIMEOUT
+    async fn check_heartbeat(
+        writer: &Arc<Mutex<Writer>>,
+        heartbeat: &Arc<Mutex<Heartbeat>>,
+    ) -> Result<bool> {
+        let mut heartbeat = heartbeat.lock().await;
+        if let Some(ping_sent_at) = heartbeat.ping_sent_at {
+            return Ok(ping_sent_at.elapsed() < PONG_TIMEOUT);
+        }
+        if heartbeat
+            .last_ping_at
+            .is_some_and(|last_ping_at| last_ping_at.elapsed() < PING_INTERVAL)
+        {
+            return Ok(true);
+        }
+
+        let payload = serde_json::to_string(&serde_json::json!({ "method": "ping" }))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        writer
+            .lock()
+            .await
+            .send(protocol::Message::Text(payload))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))?;
+
+        let now = Instant::now();
+        heartbeat.ping_sent_at = Some(now);
+        heartbeat.last_ping_at = Some(now);
+        Ok(true)
+    }
+
+    async fn reconnect(
+        url: &str,
+        writer: &Arc<Mutex<Writer>>,
+        subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+        raw_subscriptions: &RawSubscriptions,
+        heartbeat: &Arc<Mutex<Heartbeat>>,
+    ) -> Reader {
+        loop {
+            match connect_async(url).await {
+                Ok((ws_stream, _)) => {
+                    let (new_writer, reader) = ws_stream.split();
+                    *writer.lock().await = new_writer;
+                    *heartbeat.lock().await = Heartbeat::default();
+
+                    if let Err(err) =
+                        WsManager::resubscribe(writer, subscriptions, raw_subscriptions).await
+                    {
+                        error!("Error resubscribing after WS manager reconnect: {err}");
+                    }
+                    info!("WS manager reconnected");
+                    return reader;
+                }
+                Err(err) => {
+                    error!("Error reconnecting WS manager: {err}");
+                    sleep(RECONNECT_DELAY).await;
+                }
+            }
+        }
+    }
+
+    async fn resubscribe(
+        writer: &Arc<Mutex<Writer>>,
+        subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+        raw_subscriptions: &RawSubscriptions,
+    ) -> Result<()> {
+        let mut identifiers: HashSet<String> = subscriptions
+            .lock()
+            .await
+            .values()
+            .flatten()
+            .map(|subscription_data| subscription_data.identifier.clone())
+            .collect();
+        identifiers.extend(
+            raw_subscriptions
+                .lock()
+                .await
+                .iter()
+                .filter(|(_, subscription_datas)| !subscription_datas.is_empty())
+                .map(|(identifier, _)| identifier.clone()),
+        );
+
+        let mut writer = writer.lock().await;
+        for identifier in identifiers {
+            let payload = serde_json::to_string(&SubscriptionSendData {
+                method: "subscribe",
+                subscription: &serde_json::from_str::<serde_json::Value>(&identifier)
+                    .map_err(|e| Error::JsonParse(e.to_string()))?,
+            })
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+
+            writer
+                .send(protocol::Message::Text(payload))
+                .await
+                .map_err(|e| Error::Websocket(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn resnapshot_l2_books(
+        transport: &Arc<dyn Transport>,
+        subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+    ) -> Result<()> {
+        let books: Vec<(String, String)> = subscriptions
+            .lock()
+            .await
+            .keys()
+            .filter_map(|identifier| match serde_json::from_str(identifier) {
+                Ok(Subscription::L2Book { coin }) => Some((identifier.clone(), coin)),
+                _ => None,
+            })
+            .collect();
+
+        for (identifier, coin) in books {
+            let data =
+                serde_json::to_string(&serde_json::json!({ "type": "l2Book", "coin": coin }))
+                    .map_err(|e| Error::JsonParse(e.to_string()))?;
+            let return_data = transport.post("/info", data).await?;
+            let data: L2BookData =
+                serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+            let message = Message::L2Book(L2Book { data });
+
+            if let Some(subscription_datas) = subscriptions.lock().await.get(&identifier) {
+                for subscription_data in subscription_datas {
+                    subscription_data
+                        .sending_channel
+                        .send(message.clone())
+                        .map_err(|e| Error::WsSend(e.to_string()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn backfill_user_fills(
+        transport: &Arc<dyn Transport>,
+        subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+        fill_cursors: &FillCursors,
+    ) -> Result<()> {
+        let users: Vec<(String, H160)> = subscriptions
+            .lock()
+            .await
+            .keys()
+            .filter_map(|identifier| match serde_json::from_str(identifier) {
+                Ok(Subscription::UserFills { user }) => Some((identifier.clone(), user)),
+                _ => None,
+            })
+            .collect();
+
+        for (identifier, user) in users {
+            let Some(start_time) = fill_cursors
+                .lock()
+                .await
+                .get(&user)
+                .map(|cursor| cursor.time)
+            else {
+                continue;
+            };
+            let data = serde_json::to_string(&serde_json::json!({
+                "type": "userFillsByTime",
+                "user": user,
+                "startTime": start_time,
+            }))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+            let return_data = transport.post("/info", data).await?;
+            let mut fills: Vec<TradeInfo> =
+                serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+            fills.sort_by_key(|fill| fill.time);
+
+            {
+                let mut fill_cursors = fill_cursors.lock().await;
+                let cursor = fill_cursors.entry(user).or_default();
+                fills.retain(|fill| cursor.is_new(fill));
+                fills.iter().for_each(|fill| cursor.advance(fill));
+            }
+            if fills.is_empty() {
+                continue;
+            }
+
+            let message = Message::UserFills(UserFills {
+                data: UserFillsData { user, fills },
+            });
+            if let Some(subscription_datas) = subscriptions.lock().await.get(&identifier) {
+                for subscription_data in subscription_datas {
+                    subscription_data
+                        .sending_channel
+                        .send(message.clone())
+                        .map_err(|e| Error::WsSend(e.to_string()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn get_identifier(message: &Message) -> Result<String> {
         match message {
             Message::AllMids(_) => serde_json::to_string(&Subscription::AllMids)
                 .map_err(|e| Error::JsonParse(e.to_string())),
             Message::User(_) => Ok("userEvents".to_string()),
-            Message::UserFills(_) => Ok("userFills".to_string()),
+            Message::UserFills(user_fills) => serde_json::to_string(&Subscription::UserFills {
+                user: user_fills.data.user,
+            })
+            .map_err(|e| Error::JsonParse(e.to_string())),
+            Message::UserTwapSliceFills(slice_fills) => {
+                serde_json::to_string(&Subscription::UserTwapSliceFills {
+                    user: slice_fills.data.user,
+                })
+                .map_err(|e| Error::JsonParse(e.to_string()))
+            }
             Message::Trades(trades) => {
                 if trades.data.is_empty() {
                     Ok(String::default())
@@ -118,28 +600,95 @@ impl WsManager {
             .map_err(|e| Error::JsonParse(e.to_string())),
             Message::Candle(candle) => serde_json::to_string(&Subscription::Candle {
                 coin: candle.data.coin.clone(),
-                interval: candle.data.interval.clone(),
+                interval: candle.data.interval.parse()?,
             })
             .map_err(|e| Error::JsonParse(e.to_string())),
             Message::OrderUpdates(_) => Ok("orderUpdates".to_string()),
-            Message::SubscriptionResponse => Ok(String::default()),
+            Message::SubscriptionResponse(_)
+            | Message::Post(_)
+            | Message::Error(_)
+            | Message::Pong => Ok(String::default()),
         }
     }
 
     async fn parse_and_send_data(
-        data: Option<std::result::Result<protocol::Message, tungstenite::Error>>,
+        data: protocol::Message,
         subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+        raw_subscriptions: &RawSubscriptions,
+        heartbeat: &Arc<Mutex<Heartbeat>>,
+        pending_posts: &PendingPosts,
+        pending_subscribes: &PendingSubscribes,
+        fill_cursors: &FillCursors,
     ) -> Result<()> {
         let data = data
-            .ok_or(Error::ReaderDataNotFound)?
-            .map_err(|e| Error::GenericReader(e.to_string()))?
             .into_text()
             .map_err(|e| Error::ReaderTextConversion(e.to_string()))?;
         if !data.starts_with('{') {
             return Ok(());
         }
-        let message =
-            serde_json::from_str::<Message>(&data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let forwarded = WsManager::forward_raw(&data, raw_subscriptions).await;
+        let message = match serde_json::from_value::<Message>(data) {
+            Ok(message) => message,
+            // channels only raw subscribers know about
+            Err(_) if forwarded => return Ok(()),
+            Err(e) => return Err(Error::JsonParse(e.to_string())),
+        };
+        if let Message::Pong = message {
+            let mut heartbeat = heartbeat.lock().await;
+            if let Some(ping_sent_at) = heartbeat.ping_sent_at.take() {
+                heartbeat.latency = Some(ping_sent_at.elapsed());
+            }
+            return Ok(());
+        }
+        if let Message::Post(post) = message {
+            // the caller may have timed out and dropped the receiver already
+            if let Some(sender) = pending_posts.lock().await.remove(&post.data.id) {
+                let _ = sender.send(post.data.response);
+            }
+            return Ok(());
+        }
+        if let Message::SubscriptionResponse(response) = message {
+            if response.data.method == "subscribe" {
+                // raw subscriptions aren't awaited and may not canonicalize
+                if let Ok(identifier) = WsManager::canonical_identifier(response.data.subscription)
+                {
+                    if let Some(sender) = pending_subscribes.lock().await.remove(&identifier) {
+                        let _ = sender.send(Ok(()));
+                    }
+                }
+            }
+            return Ok(());
+        }
+        if let Message::Error(err) = message {
+            // Rejections quote the offending subscription, e.g. `Invalid subscription {...}`
+            let identifier = err
+                .data
+                .find('{')
+                .and_then(|start| serde_json::from_str(&err.data[start..]).ok())
+                .and_then(|subscription| WsManager::canonical_identifier(subscription).ok());
+            let sender = match identifier {
+                Some(identifier) => pending_subscribes.lock().await.remove(&identifier),
+                None => None,
+            };
+            match sender {
+                Some(sender) => {
+                    let _ = sender.send(Err(err.data));
+                }
+                None => error!("Error message received by WS manager: {}", err.data),
+            }
+            return Ok(());
+        }
+        if let Message::UserFills(user_fills) = &message {
+            let mut fill_cursors = fill_cursors.lock().await;
+            let cursor = fill_cursors.entry(user_fills.data.user).or_default();
+            user_fills
+                .data
+                .fills
+                .iter()
+                .for_each(|fill| cursor.advance(fill));
+        }
         let identifier = WsManager::get_identifier(&message)?;
         if identifier.is_empty() {
             return Ok(());
@@ -161,13 +710,155 @@ impl WsManager {
         res
     }
 
+    // Returns whether any raw subscriber listens on the message's channel. Subscribers whose
+    // receiver was dropped are removed instead of failing the message, which typed
+    // subscribers on the same channel still need to get
+    async fn forward_raw(data: &serde_json::Value, raw_subscriptions: &RawSubscriptions) -> bool {
+        let Some(channel) = data["channel"].as_str() else {
+            return false;
+        };
+        let mut raw_subscriptions = raw_subscriptions.lock().await;
+        let mut forwarded = false;
+        for subscription_datas in raw_subscriptions.values_mut() {
+            subscription_datas.retain(|subscription_data| {
+                if subscription_data.channel != channel {
+                    return true;
+                }
+                let sent = subscription_data.sending_channel.send(data.clone()).is_ok();
+                forwarded |= sent;
+                sent
+            });
+        }
+        raw_subscriptions.retain(|_, subscription_datas| !subscription_datas.is_empty());
+        forwarded
+    }
+
+    // The server echoes subscriptions back with its own field order and extra fields, so both
+    // sides go through Subscription before being compared
+    fn canonical_identifier(subscription: serde_json::Value) -> Result<String> {
+        let subscription: Subscription =
+            serde_json::from_value(subscription).map_err(|e| Error::JsonParse(e.to_string()))?;
+        serde_json::to_string(&subscription).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    async fn send_subscribe(&self, identifier: &str) -> Result<()> {
+        let key = WsManager::canonical_identifier(
+            serde_json::from_str(identifier).map_err(|e| Error::JsonParse(e.to_string()))?,
+        )?;
+        let (sender, receiver) = oneshot::channel();
+        self.pending_subscribes
+            .lock()
+            .await
+            .insert(key.clone(), sender);
+
+        let payload = serde_json::to_string(&SubscriptionSendData {
+            method: "subscribe",
+            subscription: &serde_json::from_str::<serde_json::Value>(identifier)
+                .map_err(|e| Error::JsonParse(e.to_string()))?,
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let sent = self
+            .writer
+            .lock()
+            .await
+            .send(protocol::Message::Text(payload))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()));
+        if let Err(err) = sent {
+            self.pending_subscribes.lock().await.remove(&key);
+            return Err(err);
+        }
+
+        match timeout(SUBSCRIBE_TIMEOUT, receiver).await {
+            Ok(Ok(Ok(()))) => Ok(()),
+            Ok(Ok(Err(err))) => Err(Error::SubscriptionRejected(err)),
+            Ok(Err(_)) => Err(Error::Websocket(format!(
+                "Connection dropped before subscription {identifier} was acknowledged"
+            ))),
+            Err(_) => {
+                self.pending_subscribes.lock().await.remove(&key);
+                Err(Error::Websocket(format!(
+                    "Subscription {identifier} was not acknowledged"
+                )))
+            }
+        }
+    }
+
+    async fn post(&self, request: PostRequest) -> Result<PostResponse> {
+        let id = self.post_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.pending_posts.lock().await.insert(id, sender);
+
+        let payload = serde_json::to_string(&PostSendData {
+            method: "post",
+            id,
+            request,
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let sent = self
+            .writer
+            .lock()
+            .await
+            .send(protocol::Message::Text(payload))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()));
+        if let Err(err) = sent {
+            self.pending_posts.lock().await.remove(&id);
+            return Err(err);
+        }
+
+        match timeout(POST_TIMEOUT, receiver).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(Error::Websocket(format!(
+                "Connection dropped before post request {id} was answered"
+            ))),
+            Err(_) => {
+                self.pending_posts.lock().await.remove(&id);
+                Err(Error::Websocket(format!("Post request {id} timed out")))
+            }
+        }
+    }
+
+    pub async fn post_action(
+        &self,
+        action: serde_json::Value,
+        signature: Signature,
+        nonce: u64,
+        vault_address: Option<H160>,
+    ) -> Result<ExchangeResponseStatus> {
+        let request = PostRequest::Action(PostActionPayload {
+            action,
+            signature,
+            nonce,
+            vault_address,
+        });
+        match self.post(request).await? {
+            PostResponse::Action(payload) => {
+                serde_json::from_value(payload).map_err(|e| Error::JsonParse(e.to_string()))
+            }
+            PostResponse::Error(err) => Err(Error::GenericRequest(err)),
+            PostResponse::Info(_) => Err(Error::GenericRequest(
+                "Expected an action response to post request".to_string(),
+            )),
+        }
+    }
+
+    // Returns the same body the /info http endpoint would for this request
+    pub async fn post_info(&self, body: serde_json::Value) -> Result<serde_json::Value> {
+        match self.post(PostRequest::Info(body)).await? {
+            PostResponse::Info(mut payload) => Ok(payload["data"].take()),
+            PostResponse::Error(err) => Err(Error::GenericRequest(err)),
+            PostResponse::Action(_) => Err(Error::GenericRequest(
+                "Expected an info response to post request".to_string(),
+            )),
+        }
+    }
+
     pub(crate) async fn add_subscription(
         &mut self,
         identifier: String,
         sending_channel: UnboundedSender<Message>,
     ) -> Result<u32> {
-        let mut subscriptions = self.subscriptions.lock().await;
-
         let identifier_entry = if let Subscription::UserEvents { user: _ } =
             serde_json::from_str::<Subscription>(&identifier)
                 .map_err(|e| Error::JsonParse(e.to_string()))?
@@ -181,46 +872,144 @@ impl WsManager {
         } else {
             identifier.clone()
         };
-        let subscriptions = subscriptions
-            .entry(identifier_entry.clone())
-            .or_insert(Vec::new());
+        let already_subscribed = self
+            .subscriptions
+            .lock()
+            .await
+            .get(&identifier_entry)
+            .is_some_and(|subscriptions| !subscriptions.is_empty());
 
-        if !subscriptions.is_empty() && identifier_entry.eq("userEvents") {
+        if already_subscribed && identifier_entry.eq("userEvents") {
             return Err(Error::UserEvents);
         }
 
-        if subscriptions.is_empty() {
+        // Fills from before the subscription aren't backfilled after a reconnect
+        if let Ok(Subscription::UserFills { user }) = serde_json::from_str(&identifier) {
+            self.fill_cursors
+                .lock()
+                .await
+                .entry(user)
+                .or_insert_with(|| FillCursor {
+                    time: now_timestamp_ms(),
+                    tids: HashSet::new(),
+                });
+        }
+
+        // Registered before subscribing, since the server sends the initial snapshot right
+        // after its ack and the reader would drop it without a subscriber to route it to
+        let subscription_id = self.subscription_id;
+        self.subscription_identifiers
+            .insert(subscription_id, identifier.clone());
+        self.subscriptions
+            .lock()
+            .await
+            .entry(identifier_entry.clone())
+            .or_default()
+            .push(SubscriptionData {
+                sending_channel,
+                subscription_id,
+                identifier: identifier.clone(),
+            });
+        self.subscription_id += 1;
+
+        // The reader needs the subscriptions lock to deliver data, so it is not held while
+        // waiting for the server to acknowledge
+        if !already_subscribed {
+            if let Err(err) = self.send_subscribe(&identifier).await {
+                self.subscription_identifiers.remove(&subscription_id);
+                if let Some(subscriptions) =
+                    self.subscriptions.lock().await.get_mut(&identifier_entry)
+                {
+                    subscriptions.retain(|subscription_data| {
+                        subscription_data.subscription_id != subscription_id
+                    });
+                }
+                return Err(err);
+            }
+        }
+        Ok(subscription_id)
+    }
+
+    /// Unstable: subscribes to a channel the typed layer doesn't model yet, so it may change or
+    /// go away once the channel gets a typed `Subscription`. `subscription` is sent as is, e.g.
+    /// `{"type": "webData2", "user": "0x..."}`, and every message whose `channel` equals its
+    /// `type` is forwarded as raw json, including messages meant for other subscriptions on the
+    /// same channel. Unlike `add_subscription` this doesn't wait for the server to acknowledge.
+    /// Remove it with `remove_subscription` like any other subscription.
+    pub async fn subscribe_raw(
+        &mut self,
+        subscription: serde_json::Value,
+        sending_channel: UnboundedSender<serde_json::Value>,
+    ) -> Result<u32> {
+        let channel = subscription["type"]
+            .as_str()
+            .ok_or_else(|| Error::GenericParse("Raw subscription has no type".to_string()))?
+            .to_string();
+        let identifier = subscription.to_string();
+
+        let mut raw_subscriptions = self.raw_subscriptions.lock().await;
+        let raw_subscriptions = raw_subscriptions.entry(identifier).or_default();
+        if raw_subscriptions.is_empty() {
             let payload = serde_json::to_string(&SubscriptionSendData {
                 method: "subscribe",
-                subscription: &serde_json::from_str::<serde_json::Value>(&identifier)
-                    .map_err(|e| Error::JsonParse(e.to_string()))?,
+                subscription: &subscription,
             })
             .map_err(|e| Error::JsonParse(e.to_string()))?;
-
             self.writer
+                .lock()
+                .await
                 .send(protocol::Message::Text(payload))
                 .await
                 .map_err(|e| Error::Websocket(e.to_string()))?;
         }
 
         let subscription_id = self.subscription_id;
-        self.subscription_identifiers
-            .insert(subscription_id, identifier.clone());
-        subscriptions.push(SubscriptionData {
+        raw_subscriptions.push(RawSubscriptionData {
             sending_channel,
             subscription_id,
+            channel,
         });
-
         self.subscription_id += 1;
         Ok(subscription_id)
     }
 
+    async fn remove_raw_subscription(&mut self, subscription_id: u32) -> Result<()> {
+        let mut raw_subscriptions = self.raw_subscriptions.lock().await;
+        let (identifier, subscription_datas) = raw_subscriptions
+            .iter_mut()
+            .find(|(_, subscription_datas)| {
+                subscription_datas
+                    .iter()
+                    .any(|subscription_data| subscription_data.subscription_id == subscription_id)
+            })
+            .ok_or(Error::SubscriptionNotFound)?;
+        subscription_datas
+            .retain(|subscription_data| subscription_data.subscription_id != subscription_id);
+
+        if subscription_datas.is_empty() {
+            let payload = serde_json::to_string(&SubscriptionSendData {
+                method: "unsubscribe",
+                subscription: &serde_json::from_str::<serde_json::Value>(identifier)
+                    .map_err(|e| Error::JsonParse(e.to_string()))?,
+            })
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+            let identifier = identifier.clone();
+            raw_subscriptions.remove(&identifier);
+
+            self.writer
+                .lock()
+                .await
+                .send(protocol::Message::Text(payload))
+                .await
+                .map_err(|e| Error::Websocket(e.to_string()))?;
+        }
+        Ok(())
+    }
+
     pub(crate) async fn remove_subscription(&mut self, subscription_id: u32) -> Result<()> {
-        let identifier = self
-            .subscription_identifiers
-            .get(&subscription_id)
-            .ok_or(Error::SubscriptionNotFound)?
-            .clone();
+        let Some(identifier) = self.subscription_identifiers.get(&subscription_id).cloned() else {
+            return self.remove_raw_subscription(subscription_id).await;
+        };
 
         let identifier_entry = if let Subscription::UserEvents { user: _ } =
             serde_json::from_str::<Subscription>(&identifier)
@@ -258,6 +1047,8 @@ impl WsManager {
             .map_err(|e| Error::JsonParse(e.to_string()))?;
 
             self.writer
+                .lock()
+                .await
                 .send(protocol::Message::Text(payload))
                 .await
                 .map_err(|e| Error::Websocket(e.to_string()))?;
@@ -265,3 +1056,467 @@ impl WsManager {
         Ok(())
     }
 }
+
+impl Drop for WsManager {
+    fn drop(&mut self) {
+        self.reader_handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    // The maps parse_and_send_data routes into, empty unless a test fills them in
+    #[derive(Default)]
+    struct Routes {
+        subscriptions: Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+        raw_subscriptions: RawSubscriptions,
+        heartbeat: Arc<Mutex<Heartbeat>>,
+        pending_posts: PendingPosts,
+        pending_subscribes: PendingSubscribes,
+        fill_cursors: FillCursors,
+    }
+
+    impl Routes {
+        // Routes with a single subscriber to `subscription`
+        fn subscribed(subscription: &Subscription) -> Result<(Routes, UnboundedReceiver<Message>)> {
+            let identifier =
+                serde_json::to_string(subscription).map_err(|e| Error::JsonParse(e.to_string()))?;
+            let (sending_channel, receiver) = tokio::sync::mpsc::unbounded_channel();
+            let routes = Routes {
+                subscriptions: Arc::new(Mutex::new(HashMap::from([(
+                    identifier.clone(),
+                    vec![SubscriptionData {
+                        sending_channel,
+                        subscription_id: 0,
+                        identifier,
+                    }],
+                )]))),
+                ..Default::default()
+            };
+            Ok((routes, receiver))
+        }
+
+        async fn feed(&self, message: &serde_json::Value) -> Result<()> {
+            WsManager::parse_and_send_data(
+                protocol::Message::Text(message.to_string()),
+                &self.subscriptions,
+                &self.raw_subscriptions,
+                &self.heartbeat,
+                &self.pending_posts,
+                &self.pending_subscribes,
+                &self.fill_cursors,
+            )
+            .await
+        }
+    }
+
+    // A local server for one connection that answers every subscribe with its ack, followed
+    // right away by `data`
+    async fn serve_subscribes(data: serde_json::Value) -> Result<String> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))?;
+        let address = listener
+            .local_addr()
+            .map_err(|e| Error::Websocket(e.to_string()))?;
+        spawn(async move {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let Ok(mut ws_stream) = tokio_tungstenite::accept_async(stream).await else {
+                return;
+            };
+            while let Some(Ok(protocol::Message::Text(text))) = ws_stream.next().await {
+                let Ok(request) = serde_json::from_str::<serde_json::Value>(&text) else {
+                    continue;
+                };
+                if request["method"] == "subscribe" {
+                    let ack =
+                        serde_json::json!({ "channel": "subscriptionResponse", "data": request });
+                    for message in [ack.to_string(), data.to_string()] {
+                        if ws_stream
+                            .send(protocol::Message::Text(message))
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        Ok(format!("ws://{address}"))
+    }
+
+    fn l2_book_message() -> serde_json::Value {
+        serde_json::json!({
+            "channel": "l2Book",
+            "data": { "coin": "ETH", "time": 1700000000000u64, "levels": [[], []] }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_post_response_is_routed_by_id() -> Result<()> {
+        let routes = Routes::default();
+        let (sender, receiver) = oneshot::channel();
+        routes.pending_posts.lock().await.insert(3, sender);
+
+        let data = serde_json::json!({
+            "channel": "post",
+            "data": {
+                "id": 3,
+                "response": {
+                    "type": "action",
+                    "payload": {
+                        "status": "ok",
+                        "response": { "type": "cancel", "data": { "statuses": ["success"] } }
+                    }
+                }
+            }
+        });
+        routes.feed(&data).await?;
+
+        assert!(routes.pending_posts.lock().await.is_empty());
+        let response = receiver
+            .await
+            .map_err(|e| Error::GenericRequest(e.to_string()))?;
+        assert!(matches!(response, PostResponse::Action(payload) if payload["status"] == "ok"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_post_info_request_format() -> Result<()> {
+        let request = PostSendData {
+            method: "post",
+            id: 7,
+            request: PostRequest::Info(serde_json::json!({ "type": "l2Book", "coin": "ETH" })),
+        };
+        assert_eq!(
+            serde_json::to_value(&request).map_err(|e| Error::JsonParse(e.to_string()))?,
+            serde_json::json!({
+                "method": "post",
+                "id": 7,
+                "request": { "type": "info", "payload": { "type": "l2Book", "coin": "ETH" } }
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resnapshot_l2_books_sends_http_snapshot() -> Result<()> {
+        let transport: Arc<dyn Transport> = Arc::new(
+            crate::MockTransport::new(crate::BaseUrl::Localhost).with_response(
+                "l2Book",
+                serde_json::json!({
+                    "coin": "ETH",
+                    "time": 1700000000000u64,
+                    "levels": [
+                        [{ "px": "1800.0", "sz": "1.0", "n": 1 }],
+                        [{ "px": "1801.0", "sz": "2.0", "n": 1 }]
+                    ]
+                }),
+            ),
+        );
+        let (sending_channel, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let l2_book = serde_json::to_string(&Subscription::L2Book {
+            coin: "ETH".to_string(),
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let all_mids = serde_json::to_string(&Subscription::AllMids)
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let subscriptions = Arc::new(Mutex::new(HashMap::from([
+            (
+                l2_book.clone(),
+                vec![SubscriptionData {
+                    sending_channel: sending_channel.clone(),
+                    subscription_id: 0,
+                    identifier: l2_book,
+                }],
+            ),
+            (
+                all_mids.clone(),
+                vec![SubscriptionData {
+                    sending_channel,
+                    subscription_id: 1,
+                    identifier: all_mids,
+                }],
+            ),
+        ])));
+
+        WsManager::resnapshot_l2_books(&transport, &subscriptions).await?;
+
+        match receiver.try_recv() {
+            Ok(Message::L2Book(book)) => assert_eq!(book.data.mid(), Some(1800.5)),
+            other => panic!("expected an l2 book snapshot, got {other:?}"),
+        }
+        assert!(receiver.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscription_ack_and_rejection_are_routed() -> Result<()> {
+        let routes = Routes::default();
+        let eth_book = serde_json::to_string(&Subscription::L2Book {
+            coin: "ETH".to_string(),
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let bad_book = serde_json::to_string(&Subscription::L2Book {
+            coin: "ETHH".to_string(),
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let (eth_sender, eth_receiver) = oneshot::channel();
+        let (bad_sender, bad_receiver) = oneshot::channel();
+        let mut pending_subscribes = routes.pending_subscribes.lock().await;
+        pending_subscribes.insert(eth_book, eth_sender);
+        pending_subscribes.insert(bad_book, bad_sender);
+        drop(pending_subscribes);
+
+        let messages = [
+            serde_json::json!({
+                "channel": "subscriptionResponse",
+                "data": {
+                    "method": "subscribe",
+                    "subscription": { "coin": "ETH", "nSigFigs": null, "type": "l2Book" }
+                }
+            }),
+            serde_json::json!({
+                "channel": "error",
+                "data": "Invalid subscription {\"type\":\"l2Book\",\"coin\":\"ETHH\"}"
+            }),
+        ];
+        for message in &messages {
+            routes.feed(message).await?;
+        }
+
+        assert!(routes.pending_subscribes.lock().await.is_empty());
+        assert_eq!(eth_receiver.await, Ok(Ok(())));
+        assert!(
+            matches!(bad_receiver.await, Ok(Err(err)) if err.starts_with("Invalid subscription"))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_user_fills_are_routed_and_backfilled() -> Result<()> {
+        let user = H160::from_low_u64_be(1);
+        let fill = |tid: u64, time: u64| {
+            serde_json::json!({
+                "coin": "ETH", "side": "B", "px": "1800.0", "sz": "0.1", "time": time,
+                "hash": "0x0", "startPosition": "0.0", "dir": "Open Long", "closedPnl": "0.0",
+                "oid": 1, "cloid": null, "crossed": true, "fee": "0.01", "tid": tid
+            })
+        };
+        let transport: Arc<dyn Transport> = Arc::new(
+            crate::MockTransport::new(crate::BaseUrl::Localhost).with_response(
+                "userFillsByTime",
+                serde_json::json!([fill(2, 1000), fill(3, 1000), fill(4, 1500)]),
+            ),
+        );
+
+        let (routes, mut receiver) = Routes::subscribed(&Subscription::UserFills { user })?;
+
+        let live = serde_json::json!({
+            "channel": "userFills",
+            "data": { "user": user, "fills": [fill(1, 900), fill(2, 1000)] }
+        });
+        routes.feed(&live).await?;
+        match receiver.try_recv() {
+            Ok(Message::UserFills(user_fills)) => assert_eq!(user_fills.data.fills.len(), 2),
+            other => panic!("expected live user fills, got {other:?}"),
+        }
+
+        // tid 2 was already delivered at the cursor time, only 3 and 4 are new
+        WsManager::backfill_user_fills(&transport, &routes.subscriptions, &routes.fill_cursors)
+            .await?;
+        match receiver.try_recv() {
+            Ok(Message::UserFills(user_fills)) => {
+                let tids: Vec<u64> = user_fills.data.fills.iter().map(|fill| fill.tid).collect();
+                assert_eq!(tids, vec![3, 4]);
+            }
+            other => panic!("expected backfilled user fills, got {other:?}"),
+        }
+        assert_eq!(routes.fill_cursors.lock().await[&user].time, 1500);
+
+        WsManager::backfill_user_fills(&transport, &routes.subscriptions, &routes.fill_cursors)
+            .await?;
+        assert!(receiver.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_raw_subscribers_get_unmodeled_channels() -> Result<()> {
+        let (sending_channel, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let routes = Routes {
+            raw_subscriptions: Arc::new(Mutex::new(HashMap::from([(
+                r#"{"type":"webData2","user":"0x0"}"#.to_string(),
+                vec![RawSubscriptionData {
+                    sending_channel,
+                    subscription_id: 0,
+                    channel: "webData2".to_string(),
+                }],
+            )]))),
+            ..Default::default()
+        };
+
+        let data = serde_json::json!({
+            "channel": "webData2",
+            "data": { "clearinghouseState": {}, "serverTime": 1700000000000u64 }
+        });
+        routes.feed(&data).await?;
+        assert_eq!(receiver.try_recv().ok(), Some(data));
+
+        // without a raw subscriber the unknown channel is still a parse error
+        let result = routes
+            .feed(&serde_json::json!({ "channel": "notification" }))
+            .await;
+        assert!(matches!(result, Err(Error::JsonParse(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_twap_slice_fills_are_routed_by_user() -> Result<()> {
+        let user = H160::from_low_u64_be(1);
+        let identifier = serde_json::to_string(&Subscription::UserTwapSliceFills { user })
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(
+            identifier,
+            format!(r#"{{"type":"userTwapSliceFills","user":"{user:?}"}}"#)
+        );
+        let (routes, mut receiver) =
+            Routes::subscribed(&Subscription::UserTwapSliceFills { user })?;
+
+        let data = serde_json::json!({
+            "channel": "userTwapSliceFills",
+            "data": {
+                "isSnapshot": true,
+                "user": user,
+                "twapSliceFills": [{
+                    "fill": {
+                        "coin": "ETH", "side": "B", "px": "1800.0", "sz": "0.1",
+                        "time": 1700000000000u64, "hash": "0x0", "startPosition": "0.0",
+                        "dir": "Open Long", "closedPnl": "0.0", "oid": 1, "crossed": true,
+                        "fee": "0.01", "tid": 7
+                    },
+                    "twapId": 42
+                }]
+            }
+        });
+        routes.feed(&data).await?;
+        match receiver.try_recv() {
+            Ok(Message::UserTwapSliceFills(slice_fills)) => {
+                assert!(slice_fills.data.is_snapshot);
+                assert_eq!(slice_fills.data.twap_slice_fills[0].twap_id, 42);
+                assert_eq!(slice_fills.data.twap_slice_fills[0].fill.px, "1800.0");
+            }
+            other => panic!("expected twap slice fills, got {other:?}"),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_all_mids_are_sent_whole() -> Result<()> {
+        let (routes, mut receiver) = Routes::subscribed(&Subscription::AllMids)?;
+
+        let data = serde_json::json!({
+            "channel": "allMids",
+            "data": { "mids": { "BTC": "65000.5", "ETH": "3000.25", "@107": "28.1" } }
+        });
+        routes.feed(&data).await?;
+        match receiver.try_recv() {
+            Ok(Message::AllMids(all_mids)) => {
+                assert_eq!(all_mids.data.mids.len(), 3);
+                assert_eq!(all_mids.data.mid("ETH"), Some(3000.25));
+                assert_eq!(all_mids.data.mid("@107"), Some(28.1));
+                assert_eq!(all_mids.data.mid("SOL"), None);
+            }
+            other => panic!("expected all mids, got {other:?}"),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_right_after_ack_reaches_new_subscriber() -> Result<()> {
+        let mut ws_manager = WsManager::new(serve_subscribes(l2_book_message()).await?).await?;
+        let identifier = serde_json::to_string(&Subscription::L2Book {
+            coin: "ETH".to_string(),
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let (sending_channel, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        ws_manager
+            .add_subscription(identifier, sending_channel)
+            .await?;
+
+        match timeout(Duration::from_secs(5), receiver.recv()).await {
+            Ok(Some(Message::L2Book(book))) => assert_eq!(book.data.coin, "ETH"),
+            other => panic!("expected the l2 book snapshot, got {other:?}"),
+        }
+        ws_manager.close().await
+    }
+
+    #[tokio::test]
+    async fn test_dropped_raw_receiver_does_not_block_typed_routing() -> Result<()> {
+        let (routes, mut receiver) = Routes::subscribed(&Subscription::L2Book {
+            coin: "ETH".to_string(),
+        })?;
+        let (sending_channel, raw_receiver) = tokio::sync::mpsc::unbounded_channel();
+        routes.raw_subscriptions.lock().await.insert(
+            r#"{"type":"l2Book","coin":"ETH"}"#.to_string(),
+            vec![RawSubscriptionData {
+                sending_channel,
+                subscription_id: 1,
+                channel: "l2Book".to_string(),
+            }],
+        );
+        drop(raw_receiver);
+
+        routes.feed(&l2_book_message()).await?;
+        assert!(matches!(receiver.try_recv(), Ok(Message::L2Book(_))));
+        assert!(routes.raw_subscriptions.lock().await.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pong_records_latency() -> Result<()> {
+        let routes = Routes::default();
+        routes.heartbeat.lock().await.ping_sent_at =
+            Some(Instant::now() - Duration::from_millis(20));
+
+        routes
+            .feed(&serde_json::json!({ "channel": "pong" }))
+            .await?;
+        let heartbeat = routes.heartbeat.lock().await;
+        assert!(heartbeat.ping_sent_at.is_none());
+        assert!(heartbeat
+            .latency
+            .is_some_and(|latency| latency >= Duration::from_millis(20)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_close_drops_subscriber_channels() -> Result<()> {
+        let mut ws_manager = WsManager::new(serve_subscribes(l2_book_message()).await?).await?;
+        let identifier = serde_json::to_string(&Subscription::L2Book {
+            coin: "ETH".to_string(),
+        })
+        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let (sending_channel, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        ws_manager
+            .add_subscription(identifier, sending_channel)
+            .await?;
+        assert!(matches!(
+            timeout(Duration::from_secs(5), receiver.recv()).await,
+            Ok(Some(Message::L2Book(_)))
+        ));
+
+        ws_manager.close().await?;
+        // no sender is left once the reader is stopped and the subscriptions are cleared
+        assert!(matches!(
+            timeout(Duration::from_secs(5), receiver.recv()).await,
+            Ok(None)
+        ));
+        Ok(())
+    }
+}
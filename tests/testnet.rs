@@ -0,0 +1,99 @@
+// Round trips against the live testnet exchange. Only runs when HYPERLIQUID_TESTNET_KEY holds
+// the private key of a funded testnet account, otherwise every test returns early:
+//
+//   HYPERLIQUID_TESTNET_KEY=<hex key> cargo test --test testnet
+
+use ethers::signers::{LocalWallet, Signer};
+use hyperliquid_rust_sdk::{
+    BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
+    ExchangeDataStatus, ExchangeResponseStatus, InfoClient, Side,
+};
+
+const TESTNET_KEY_VAR: &str = "HYPERLIQUID_TESTNET_KEY";
+const COIN: &str = "ETH";
+// Testnet enforces the same $10 minimum order value as mainnet
+const MIN_NOTIONAL: f64 = 11.0;
+
+fn testnet_wallet() -> Option<LocalWallet> {
+    let key = std::env::var(TESTNET_KEY_VAR)
+        .ok()
+        .filter(|key| !key.is_empty())?;
+    Some(
+        key.trim_start_matches("0x")
+            .parse()
+            .unwrap_or_else(|e| panic!("{TESTNET_KEY_VAR} is not a valid private key: {e}")),
+    )
+}
+
+#[tokio::test]
+async fn place_and_cancel_resting_order() {
+    let Some(wallet) = testnet_wallet() else {
+        eprintln!("{TESTNET_KEY_VAR} not set, skipping testnet round trip");
+        return;
+    };
+    let address = wallet.address();
+
+    let exchange_client = ExchangeClient::new(None, wallet, Some(BaseUrl::Testnet), None, None)
+        .await
+        .unwrap();
+    let info_client = InfoClient::new(None, Some(BaseUrl::Testnet)).await.unwrap();
+
+    let sz_decimals = exchange_client
+        .meta
+        .universe
+        .iter()
+        .find(|asset| asset.name == COIN)
+        .unwrap()
+        .sz_decimals;
+    let mid: f64 = info_client.all_mids().await.unwrap()[COIN].parse().unwrap();
+    // Far enough below the mid that the order rests instead of filling
+    let limit_px = (mid * 0.5).round();
+    let sz_factor = 10f64.powi(sz_decimals as i32);
+    let sz = (MIN_NOTIONAL / limit_px * sz_factor).ceil() / sz_factor;
+
+    let order = ClientOrderRequest {
+        asset: COIN.to_string(),
+        side: Side::Bid,
+        reduce_only: false,
+        limit_px,
+        sz,
+        cloid: None,
+        order_type: ClientOrder::Limit(ClientLimit {
+            tif: "Gtc".to_string(),
+        }),
+    };
+    let response = exchange_client.order(order, None).await.unwrap();
+    let oid = match &response {
+        ExchangeResponseStatus::Ok(exchange_response) => {
+            match &exchange_response.data.as_ref().unwrap().statuses[0] {
+                ExchangeDataStatus::Resting(order) => order.oid,
+                status => panic!("expected a resting order, got {status:?}"),
+            }
+        }
+        ExchangeResponseStatus::Err(e) => panic!("order rejected: {e}"),
+    };
+
+    let open_orders = info_client.open_orders(address).await.unwrap();
+    assert!(open_orders.iter().any(|order| order.oid == oid));
+
+    let response = exchange_client
+        .cancel(
+            ClientCancelRequest {
+                asset: COIN.to_string(),
+                oid,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    match response {
+        ExchangeResponseStatus::Ok(exchange_response) => assert!(matches!(
+            exchange_response.data.unwrap().statuses[0],
+            ExchangeDataStatus::Success
+        )),
+        ExchangeResponseStatus::Err(e) => panic!("cancel rejected: {e}"),
+    }
+
+    let open_orders = info_client.open_orders(address).await.unwrap();
+    assert!(open_orders.iter().all(|order| order.oid != oid));
+}